@@ -1,3 +1,5 @@
 //! Yew Components
 
 pub mod query_client_provider;
+pub mod query_error_boundary;
+pub mod query_error_reset_boundary;
@@ -35,11 +35,13 @@ pub struct Props {
 /// need it.
 #[function_component(QueryClientProvider)]
 pub fn query_client_provider(props: &Props) -> Html {
-    html! {
+    #[allow(clippy::unnecessary_operation)] // false positive in the `html!` expansion
+    let html = html! {
         <ContextProvider<Hidden> context={Hidden(props.client.clone())}>
             {for props.children.iter()}
         </ContextProvider<Hidden>>
-    }
+    };
+    html
 }
 
 /// Gets the [`QueryClient`][] from the context.
@@ -0,0 +1,209 @@
+//! Shows a fallback in place of components whose queries have failed.
+//!
+//! Queries using the [`throw_on_error`][crate::query_client::Options::throw_on_error] option
+//! report their errors to the nearest enclosing [`QueryErrorBoundary`][], rather than
+//! leaving them for the component using the query to handle. The boundary then shows
+//! its `fallback` in place of its children, until it is reset with [`QueryError::reset`][]
+//! (or by an enclosing [`QueryErrorResetBoundary`][crate::components::query_error_reset_boundary::QueryErrorResetBoundary]),
+//! which forgets the errors, refetches the failed queries, and shows the children again.
+//!
+//! Errors are reported by [`use_query`][crate::hooks::use_query::use_query] and the hooks
+//! built on it. If there is no boundary, the error is left for the component to handle.
+//!
+//! ```no_run
+//! use std::future::Future;
+//! use std::pin::Pin;
+//! use yew::prelude::*;
+//! use yew_query::prelude::*;
+//! use yew_query::query_client::ThrowOnError;
+//!
+//! struct ApiError(String);
+//!
+//! #[derive(Clone, Eq, PartialEq, Hash)]
+//! struct GetTodos;
+//!
+//! impl TryQuery for GetTodos {
+//!     type Ok = Vec<String>;
+//!     type Error = ApiError;
+//!     type Future = Pin<Box<dyn Future<Output = Result<Vec<String>, ApiError>>>>;
+//!
+//!     fn try_query(&self) -> Self::Future {
+//!         Box::pin(async move {
+//!             todo!("...Making some API calls here...")
+//!         })
+//!     }
+//! }
+//!
+//! #[function_component(TodoList)]
+//! fn todo_list() -> Html {
+//!     let options = use_state(|| QueryOptions {
+//!         throw_on_error: Some(ThrowOnError::Always),
+//!         ..Default::default()
+//!     });
+//!     let todos = use_query_with_options(GetTodos, (*options).clone());
+//!     html! {
+//!         <ul>
+//!             {for todos.data().into_iter().flatten().map(|todo| html! { <li>{todo}</li> })}
+//!         </ul>
+//!     }
+//! }
+//!
+//! #[function_component(App)]
+//! fn app() -> Html {
+//!     let fallback = use_state(|| {
+//!         QueryErrorFallback::new(|error| html! {
+//!             <div>
+//!                 if let Some(ApiError(message)) = error.downcast_ref::<ApiError>() {
+//!                     <p>{message}</p>
+//!                 }
+//!                 <button onclick={error.reset()}>{"Try again"}</button>
+//!             </div>
+//!         })
+//!     });
+//!     html! {
+//!         <QueryErrorBoundary fallback={(*fallback).clone()}>
+//!             <TodoList />
+//!         </QueryErrorBoundary>
+//!     }
+//! }
+//! ```
+use super::query_error_reset_boundary::use_query_error_resets;
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+use yew::prelude::*;
+
+/// The error of a failed query, reported to a [`QueryErrorBoundary`][].
+#[derive(Clone)]
+pub struct QueryError {
+    error: Rc<dyn Any>,
+    reset: Rc<dyn Fn()>,
+}
+
+impl QueryError {
+    /// An error which is reset by calling `reset`.
+    pub(crate) fn new(error: Rc<dyn Any>, reset: impl Fn() + 'static) -> Self {
+        Self {
+            error,
+            reset: Rc::new(reset),
+        }
+    }
+
+    /// The error, if it is of type `E` (the [`Error`][crate::query::TryQuery::Error]
+    /// type of the query which failed).
+    pub fn downcast_ref<E: 'static>(&self) -> Option<&E> {
+        self.error.downcast_ref()
+    }
+
+    /// Creates a callback which forgets the error and refetches the failed query.
+    ///
+    /// When given to a [`QueryErrorBoundary`][]'s fallback, this resets every query
+    /// reported to the boundary, and shows the boundary's children again.
+    pub fn reset<IN>(&self) -> Callback<IN> {
+        let reset = self.reset.clone();
+        Callback::from(move |_| reset())
+    }
+}
+
+/// Renders the fallback of a [`QueryErrorBoundary`][] from the error reported to it.
+#[derive(Clone)]
+pub struct QueryErrorFallback(Rc<dyn Fn(QueryError) -> Html>);
+
+impl QueryErrorFallback {
+    pub fn new(render: impl Fn(QueryError) -> Html + 'static) -> Self {
+        Self(Rc::new(render))
+    }
+}
+
+impl PartialEq for QueryErrorFallback {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct Hidden(Callback<QueryError>);
+
+/// Properties for [`QueryErrorBoundary`][]
+#[derive(PartialEq, Properties)]
+pub struct Props {
+    /// Renders the error of the first query reported to the boundary, in place of
+    /// the children.
+    pub fallback: QueryErrorFallback,
+    /// Children whose queries report their errors to the boundary.
+    #[prop_or_default]
+    pub children: Children,
+}
+
+/// Shows a fallback in place of its children when one of their queries fails.
+///
+/// See the [module-level documentation][self] for more information.
+#[function_component(QueryErrorBoundary)]
+pub fn query_error_boundary(props: &Props) -> Html {
+    let shown = use_state(|| None::<QueryError>);
+    let (report, reset) = &*use_ref({
+        let shown = shown.clone();
+        move || {
+            // Every error reported since the boundary was last reset.
+            let errors = Rc::new(RefCell::new(Vec::<QueryError>::new()));
+            let reset: Rc<dyn Fn()> = {
+                let errors = errors.clone();
+                let shown = shown.clone();
+                Rc::new(move || {
+                    let errors = std::mem::take(&mut *errors.borrow_mut());
+                    if !errors.is_empty() {
+                        for error in errors {
+                            (error.reset)();
+                        }
+                        shown.set(None);
+                    }
+                })
+            };
+            let report = {
+                let reset = reset.clone();
+                Callback::from(move |error: QueryError| {
+                    let mut errors = errors.borrow_mut();
+                    errors.push(error.clone());
+                    if errors.len() == 1 {
+                        shown.set(Some(QueryError {
+                            error: error.error,
+                            reset: reset.clone(),
+                        }));
+                    }
+                })
+            };
+            (report, reset)
+        }
+    });
+
+    // Reset along with the enclosing `QueryErrorResetBoundary`.
+    let resets = use_query_error_resets();
+    use_effect_with_deps(
+        {
+            let reset = reset.clone();
+            move |_| {
+                reset();
+                || ()
+            }
+        },
+        resets,
+    );
+
+    match &*shown {
+        Some(error) => (props.fallback.0)(error.clone()),
+        None => {
+            #[allow(clippy::unnecessary_operation)] // false positive in the `html!` expansion
+            let html = html! {
+                <ContextProvider<Hidden> context={Hidden(report.clone())}>
+                    {for props.children.iter()}
+                </ContextProvider<Hidden>>
+            };
+            html
+        }
+    }
+}
+
+/// Gets the callback to report errors to the enclosing [`QueryErrorBoundary`][], if any.
+pub(crate) fn use_query_error_boundary() -> Option<Callback<QueryError>> {
+    use_context::<Hidden>().map(|hidden| hidden.0)
+}
@@ -0,0 +1,95 @@
+//! Resets every [`QueryErrorBoundary`][] within it at once.
+//!
+//! Each [`QueryErrorBoundary`][] can be reset on its own by its fallback (see
+//! [`QueryError::reset`][crate::components::query_error_boundary::QueryError::reset]).
+//! A [`QueryErrorResetBoundary`][] allows them to be reset together from anywhere
+//! inside it, such as from a single "Try again" button, using the
+//! [`use_query_error_reset_boundary`][] hook. Resetting forgets the errors of every query
+//! reported to the boundaries, refetches them, and shows the boundaries' children again.
+//!
+//! ```no_run
+//! use yew::prelude::*;
+//! use yew_query::prelude::*;
+//!
+//! #[function_component(TryAgain)]
+//! fn try_again() -> Html {
+//!     let reset = use_query_error_reset_boundary();
+//!     html! {
+//!         <button onclick={reset}>{"Try again"}</button>
+//!     }
+//! }
+//!
+//! #[function_component(Dashboard)]
+//! fn dashboard() -> Html {
+//!     let fallback = use_state(|| {
+//!         QueryErrorFallback::new(|_| html! { <p>{"Something went wrong"}</p> })
+//!     });
+//!     html! {
+//!         <QueryErrorResetBoundary>
+//!             <TryAgain />
+//!             <QueryErrorBoundary fallback={(*fallback).clone()}>
+//!                 // ...Components using queries...
+//!             </QueryErrorBoundary>
+//!             <QueryErrorBoundary fallback={(*fallback).clone()}>
+//!                 // ...More components using queries...
+//!             </QueryErrorBoundary>
+//!         </QueryErrorResetBoundary>
+//!     }
+//! }
+//! ```
+//!
+//! [`QueryErrorBoundary`]: crate::components::query_error_boundary::QueryErrorBoundary
+use yew::prelude::*;
+
+#[derive(Clone, PartialEq)]
+struct Hidden {
+    /// How many times the boundary has been reset.
+    resets: usize,
+    reset: Callback<()>,
+}
+
+/// Properties for [`QueryErrorResetBoundary`][]
+#[derive(PartialEq, Properties)]
+pub struct Props {
+    /// Children containing the [`QueryErrorBoundary`][crate::components::query_error_boundary::QueryErrorBoundary]s
+    /// to be reset.
+    #[prop_or_default]
+    pub children: Children,
+}
+
+/// Allows every [`QueryErrorBoundary`][crate::components::query_error_boundary::QueryErrorBoundary]
+/// within it to be reset at once.
+///
+/// See the [module-level documentation][self] for more information.
+#[function_component(QueryErrorResetBoundary)]
+pub fn query_error_reset_boundary(props: &Props) -> Html {
+    let resets = use_state(|| 0);
+    let reset = {
+        let resets = resets.clone();
+        Callback::from(move |()| resets.set(*resets + 1))
+    };
+    #[allow(clippy::unnecessary_operation)] // false positive in the `html!` expansion
+    let html = html! {
+        <ContextProvider<Hidden> context={Hidden { resets: *resets, reset }}>
+            {for props.children.iter()}
+        </ContextProvider<Hidden>>
+    };
+    html
+}
+
+/// Creates a callback which resets the enclosing [`QueryErrorResetBoundary`][].
+///
+/// If there is no [`QueryErrorResetBoundary`][], the callback does nothing.
+pub fn use_query_error_reset_boundary<IN>() -> Callback<IN> {
+    match use_context::<Hidden>() {
+        Some(hidden) => hidden.reset.reform(|_| ()),
+        None => Callback::noop(),
+    }
+}
+
+/// How many times the enclosing [`QueryErrorResetBoundary`][] has been reset, which is
+/// watched by each [`QueryErrorBoundary`][crate::components::query_error_boundary::QueryErrorBoundary]
+/// within it.
+pub(crate) fn use_query_error_resets() -> usize {
+    use_context::<Hidden>().map_or(0, |hidden| hidden.resets)
+}
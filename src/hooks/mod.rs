@@ -6,4 +6,8 @@
 //! [Function Components]: https://yew.rs/docs/concepts/function-components/introduction
 //! [`QueryClient`]: crate::query_client::QueryClient
 
+pub mod use_infinite_query;
+pub mod use_mutation;
+pub mod use_queries;
 pub mod use_query;
+pub mod use_query_select;
@@ -0,0 +1,209 @@
+//! Yew hook to fetch infinite queries.
+//!
+//! The [`use_infinite_query`][] hook works like [`use_query`][crate::hooks::use_query::use_query],
+//! but for an [`InfiniteQuery`][], whose data is loaded a page at a time. The first page is
+//! fetched automatically, and further pages are fetched on request using the returned
+//! [`UseInfiniteQueryHandle`][]. All of the loaded pages are kept together in a single
+//! entry of the [`QueryClient`][].
+//!
+//! ```no_run
+//! use std::future::Future;
+//! use std::pin::Pin;
+//! use yew::prelude::*;
+//! use yew_query::prelude::*;
+//!
+//! struct Page {
+//!     todos: Vec<String>,
+//!     next_cursor: Option<u32>,
+//! }
+//!
+//! #[derive(Clone, Eq, PartialEq, Hash)]
+//! struct GetTodos;
+//!
+//! impl InfiniteQuery for GetTodos {
+//!     type PageParam = u32;
+//!     type Ok = Page;
+//!     type Error = String;
+//!     type Future = Pin<Box<dyn Future<Output = Result<Page, String>>>>;
+//!
+//!     fn initial_page_param(&self) -> u32 {
+//!         0
+//!     }
+//!
+//!     fn query_page(&self, cursor: &u32) -> Self::Future {
+//!         Box::pin(async move {
+//!             todo!("...Making some API calls here...")
+//!         })
+//!     }
+//!
+//!     fn next_page_param(&self, last_page: &Page) -> Option<u32> {
+//!         last_page.next_cursor
+//!     }
+//! }
+//!
+//! #[function_component(TodoList)]
+//! fn todo_list() -> Html {
+//!     let todos = use_infinite_query(GetTodos);
+//!     let onclick = {
+//!         let todos = todos.clone();
+//!         Callback::from(move |_| todos.fetch_next_page())
+//!     };
+//!     html! {
+//!         <div>
+//!             {for todos.pages().flat_map(|page| page.todos.iter()).map(|todo| html! { <p>{todo}</p> })}
+//!             if todos.has_next_page() {
+//!                 <button {onclick} disabled={todos.is_fetching_next_page()}>
+//!                     {"Load more"}
+//!                 </button>
+//!             }
+//!         </div>
+//!     }
+//! }
+//! ```
+//!
+use std::ops::Deref;
+
+use super::use_query::{use_query_with_options, Options, QueryResult};
+use crate::components::query_client_provider::use_opt_query_client;
+use crate::query::infinite::{Direction, Infinite, InfiniteData, InfiniteQuery};
+use crate::query_client::runtime::spawn_local;
+use crate::query_client::QueryClient;
+
+/// Reflects the state of an infinite query, and allows more pages to be fetched.
+///
+/// Dereferences to the [`QueryResult`][] of the underlying [`Infinite`][] query.
+pub struct UseInfiniteQueryHandle<I>
+where
+    I: InfiniteQuery + Clone + 'static,
+{
+    query: I,
+    options: Options,
+    client: Option<QueryClient>,
+    result: QueryResult<Infinite<I>>,
+}
+
+impl<I> Clone for UseInfiniteQueryHandle<I>
+where
+    I: InfiniteQuery + Clone + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            query: self.query.clone(),
+            options: self.options.clone(),
+            client: self.client.clone(),
+            result: self.result.clone(),
+        }
+    }
+}
+
+impl<I> Deref for UseInfiniteQueryHandle<I>
+where
+    I: InfiniteQuery + Clone + 'static,
+{
+    type Target = QueryResult<Infinite<I>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.result
+    }
+}
+
+impl<I> UseInfiniteQueryHandle<I>
+where
+    I: InfiniteQuery + Clone + 'static,
+{
+    /// The loaded pages, in order.
+    pub fn pages(&self) -> impl Iterator<Item = &I::Ok> {
+        self.result
+            .data()
+            .into_iter()
+            .flat_map(|data: &InfiniteData<I>| data.pages.iter().map(AsRef::as_ref))
+    }
+
+    /// Whether there is a page after those already loaded.
+    pub fn has_next_page(&self) -> bool {
+        self.result
+            .data()
+            .and_then(|data| data.next_page_param(&self.query))
+            .is_some()
+    }
+
+    /// Whether there is a page before those already loaded.
+    pub fn has_previous_page(&self) -> bool {
+        self.result
+            .data()
+            .and_then(|data| data.previous_page_param(&self.query))
+            .is_some()
+    }
+
+    /// Whether the page after those already loaded is being fetched, by this or any
+    /// other user of the query.
+    pub fn is_fetching_next_page(&self) -> bool {
+        self.fetching_page() == Some(Direction::Next)
+    }
+
+    /// Whether the page before those already loaded is being fetched, by this or any
+    /// other user of the query.
+    pub fn is_fetching_previous_page(&self) -> bool {
+        self.fetching_page() == Some(Direction::Previous)
+    }
+
+    fn fetching_page(&self) -> Option<Direction> {
+        self.result
+            .cached()
+            .and_then(|cached| cached.fetching_page())
+    }
+
+    /// Fetches the page after those already loaded, if there is one.
+    ///
+    /// See [`QueryClient::fetch_next_page`][].
+    pub fn fetch_next_page(&self) {
+        if let Some(client) = self.client.clone() {
+            let query = self.query.clone();
+            let options = self.options.clone();
+            spawn_local(async move {
+                client.fetch_next_page(query, &options).await;
+            });
+        }
+    }
+
+    /// Fetches the page before those already loaded, if there is one.
+    ///
+    /// See [`QueryClient::fetch_previous_page`][].
+    pub fn fetch_previous_page(&self) {
+        if let Some(client) = self.client.clone() {
+            let query = self.query.clone();
+            let options = self.options.clone();
+            spawn_local(async move {
+                client.fetch_previous_page(query, &options).await;
+            });
+        }
+    }
+}
+
+/// Makes an infinite query.
+///
+/// See the [module-level documentation][self] for more information.
+pub fn use_infinite_query_with_options<I>(query: I, options: Options) -> UseInfiniteQueryHandle<I>
+where
+    I: InfiniteQuery + Clone + 'static,
+{
+    let client = use_opt_query_client();
+    let result = use_query_with_options(Infinite(query.clone()), options.clone());
+
+    UseInfiniteQueryHandle {
+        query,
+        options,
+        client,
+        result: (*result).clone(),
+    }
+}
+
+/// Makes an infinite query using the default options.
+///
+/// See the [module-level documentation][self] for more information.
+pub fn use_infinite_query<I>(query: I) -> UseInfiniteQueryHandle<I>
+where
+    I: InfiniteQuery + Clone + 'static,
+{
+    use_infinite_query_with_options(query, Options::default())
+}
@@ -0,0 +1,226 @@
+//! Yew hook to perform mutations.
+//!
+//! The [`use_mutation`][] hook returns a [`UseMutationHandle`][], which can be used to
+//! perform a [`Mutation`][] and track its state. Once the mutation completes, its
+//! callbacks are called with the [`QueryClient`][], so that affected queries may be
+//! updated. Queries may also be updated optimistically before the mutation completes
+//! (see [`Mutation::on_mutate`][]), in which case they are restored automatically if
+//! the mutation fails.
+//!
+//! ```no_run
+//! use std::future::Future;
+//! use std::pin::Pin;
+//! use yew::prelude::*;
+//! use yew_query::prelude::*;
+//!
+//! # #[derive(Clone, Eq, PartialEq, Hash)]
+//! # struct GetTodos;
+//! # impl Query for GetTodos {
+//! #     type Output = Vec<String>;
+//! #     type Future = Pin<Box<dyn Future<Output = Self::Output>>>;
+//! #     fn query(&self) -> Self::Future { todo!() }
+//! # }
+//! struct AddTodo;
+//!
+//! impl Mutation for AddTodo {
+//!     type Variables = String;
+//!     type Ok = ();
+//!     type Error = String;
+//!     type Future = Pin<Box<dyn Future<Output = Result<(), String>>>>;
+//!
+//!     fn mutate(&self, title: &String) -> Self::Future {
+//!         Box::pin(async move {
+//!             todo!("...Making some API calls here...")
+//!         })
+//!     }
+//!
+//!     fn on_success(&self, client: &QueryClient, _: &(), _: &String) {
+//!         client.invalidate_query(&GetTodos);
+//!     }
+//! }
+//!
+//! #[function_component(AddTodoButton)]
+//! fn add_todo_button() -> Html {
+//!     let add_todo = use_mutation(AddTodo);
+//!     let onclick = {
+//!         let add_todo = add_todo.clone();
+//!         Callback::from(move |_| add_todo.mutate(String::from("Write docs")))
+//!     };
+//!     html! {
+//!         <button {onclick} disabled={add_todo.is_pending()}>
+//!             {"Add todo"}
+//!         </button>
+//!     }
+//! }
+//! ```
+//!
+use std::cell::Cell;
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::components::query_client_provider::use_query_client;
+use crate::mutation::Mutation;
+use crate::query_client::runtime::spawn_local;
+use crate::query_client::QueryClient;
+
+enum MutationState<M>
+where
+    M: Mutation,
+{
+    Idle,
+    Pending,
+    Success(Rc<M::Ok>),
+    Error(Rc<M::Error>),
+}
+
+impl<M> Clone for MutationState<M>
+where
+    M: Mutation,
+{
+    fn clone(&self) -> Self {
+        match self {
+            Self::Idle => Self::Idle,
+            Self::Pending => Self::Pending,
+            Self::Success(data) => Self::Success(data.clone()),
+            Self::Error(error) => Self::Error(error.clone()),
+        }
+    }
+}
+
+/// Performs a mutation, and reflects the state of its most recent attempt.
+pub struct UseMutationHandle<M>
+where
+    M: Mutation,
+{
+    mutation: Rc<M>,
+    client: QueryClient,
+    state: UseStateHandle<MutationState<M>>,
+    /// Identifies the most recent call to `mutate`, so that the results of earlier
+    /// calls (or calls from before a `reset`) are ignored.
+    generation: Rc<Cell<usize>>,
+}
+
+impl<M> Clone for UseMutationHandle<M>
+where
+    M: Mutation,
+{
+    fn clone(&self) -> Self {
+        Self {
+            mutation: self.mutation.clone(),
+            client: self.client.clone(),
+            state: self.state.clone(),
+            generation: self.generation.clone(),
+        }
+    }
+}
+
+impl<M> UseMutationHandle<M>
+where
+    M: Mutation + 'static,
+{
+    /// Performs the mutation with the given variables.
+    ///
+    /// If the mutation is already pending, the new attempt replaces it, and the
+    /// result of the earlier attempt is ignored (though its callbacks are still called).
+    pub fn mutate(&self, variables: M::Variables) {
+        let generation = self.generation.get() + 1;
+        self.generation.set(generation);
+        self.state.set(MutationState::Pending);
+
+        let update = self.client.optimistic_update();
+        self.mutation.on_mutate(&update, &variables);
+
+        let handle = self.clone();
+        spawn_local(async move {
+            let result = handle.mutation.mutate(&variables).await;
+            match &result {
+                Ok(data) => {
+                    update.commit();
+                    handle.mutation.on_success(&handle.client, data, &variables);
+                }
+                Err(error) => {
+                    update.rollback();
+                    handle.mutation.on_error(&handle.client, error, &variables);
+                }
+            }
+            handle
+                .mutation
+                .on_settled(&handle.client, result.as_ref(), &variables);
+            if handle.generation.get() == generation {
+                handle.state.set(match result {
+                    Ok(data) => MutationState::Success(Rc::new(data)),
+                    Err(error) => MutationState::Error(Rc::new(error)),
+                });
+            }
+        });
+    }
+
+    /// Returns the mutation to its initial state, forgetting the result of the
+    /// most recent attempt.
+    pub fn reset(&self) {
+        self.generation.set(self.generation.get() + 1);
+        self.state.set(MutationState::Idle);
+    }
+}
+
+impl<M> UseMutationHandle<M>
+where
+    M: Mutation,
+{
+    pub fn is_idle(&self) -> bool {
+        matches!(*self.state, MutationState::Idle)
+    }
+
+    pub fn is_pending(&self) -> bool {
+        matches!(*self.state, MutationState::Pending)
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(*self.state, MutationState::Success(..))
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(*self.state, MutationState::Error(..))
+    }
+
+    /// The result of the most recent attempt, if it succeeded.
+    pub fn data(&self) -> Option<&M::Ok> {
+        match &*self.state {
+            MutationState::Success(data) => Some(data.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// The error from the most recent attempt, if it failed.
+    pub fn error(&self) -> Option<&M::Error> {
+        match &*self.state {
+            MutationState::Error(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Prepares a mutation to be performed.
+///
+/// See the [module-level documentation][self] for more information.
+///
+/// # Panics
+///
+/// This hook will panic if no [`QueryClient`][] is provided, as the mutation's callbacks
+/// require one.
+pub fn use_mutation<M>(mutation: M) -> UseMutationHandle<M>
+where
+    M: Mutation + 'static,
+{
+    let client = use_query_client();
+    let state = use_state(|| MutationState::Idle);
+    let generation = use_ref(|| Cell::new(0));
+
+    UseMutationHandle {
+        mutation: Rc::new(mutation),
+        client,
+        state,
+        generation,
+    }
+}
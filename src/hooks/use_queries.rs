@@ -0,0 +1,227 @@
+//! Yew hook to fetch a dynamic list of queries.
+//!
+//! Hooks cannot be called in a loop, so [`use_query`][crate::hooks::use_query::use_query]
+//! cannot be used to fetch a list of queries whose length changes between renders.
+//! Instead, the [`use_queries`][] hook fetches and observes every query in a list,
+//! returning a [`QueryResult`][] for each, in the same order.
+//!
+//! ```no_run
+//! use std::future::Future;
+//! use std::pin::Pin;
+//! use yew::prelude::*;
+//! use yew_query::prelude::*;
+//!
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! #[derive(Clone, Eq, PartialEq, Hash)]
+//! struct GetUser(u32);
+//!
+//! impl Query for GetUser {
+//!     type Output = User;
+//!     type Future = Pin<Box<dyn Future<Output = Self::Output>>>;
+//!
+//!     fn query(&self) -> Self::Future {
+//!         Box::pin(async move {
+//!             todo!("...Making some API calls here...")
+//!         })
+//!     }
+//! }
+//!
+//! #[derive(Properties, PartialEq)]
+//! struct Props {
+//!     user_ids: Vec<u32>,
+//! }
+//!
+//! #[function_component(UserNames)]
+//! fn user_names(props: &Props) -> Html {
+//!     let queries = props.user_ids.iter().copied().map(GetUser).collect();
+//!     let users = use_queries(queries, QueryOptions::default());
+//!     html! {
+//!         <ul>
+//!             {for users.iter().map(|user| match user.data() {
+//!                 Some(user) => html! { <li>{&user.name}</li> },
+//!                 None => html! { <li>{"Loading..."}</li> },
+//!             })}
+//!         </ul>
+//!     }
+//! }
+//! ```
+//!
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use super::use_query::{observe_query, Options, QueryObserver, QueryResult};
+use crate::components::query_client_provider::use_opt_query_client;
+use crate::prelude::QueryClient;
+use crate::query::TryQuery;
+use crate::query_client::OptionsKey;
+
+/// The results of a list of queries, by query, which are updated one at a time.
+struct QueryResults<Q>(HashMap<Q, QueryResult<Q>>)
+where
+    Q: TryQuery;
+
+enum QueryResultsAction<Q>
+where
+    Q: TryQuery,
+{
+    /// Updates the result of a query.
+    Update(Q, Box<QueryResult<Q>>),
+    /// Forgets the results of queries which are no longer observed.
+    Remove(Vec<Q>),
+}
+
+impl<Q> Reducible for QueryResults<Q>
+where
+    Q: TryQuery + Clone,
+{
+    type Action = QueryResultsAction<Q>;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut results = self.0.clone();
+        match action {
+            QueryResultsAction::Update(query, result) => {
+                results.insert(query, *result);
+            }
+            QueryResultsAction::Remove(queries) => {
+                for query in &queries {
+                    results.remove(query);
+                }
+            }
+        }
+        Rc::new(Self(results))
+    }
+}
+
+/// Observes each query of a list, for as long as it remains in the list.
+struct Observers<Q> {
+    /// The client and options with which the queries are observed. If either
+    /// changes, every query is observed again.
+    source: Option<(QueryClient, OptionsKey)>,
+    observers: HashMap<Q, QueryObserver>,
+}
+
+/// Makes a query for every item of a list.
+///
+/// See the [module-level documentation][self] for more information.
+pub fn use_queries<Q>(queries: Vec<Q>, options: Options) -> Vec<QueryResult<Q>>
+where
+    Q: TryQuery + Clone + 'static,
+{
+    let client = use_opt_query_client();
+    let results = use_reducer(|| QueryResults(HashMap::new()));
+    // As with `use_query`, the options are replaced on every render, so that the
+    // latest closures are used.
+    let shared_options = use_mut_ref(|| options.clone());
+    *shared_options.borrow_mut() = options.clone();
+    let observers = use_mut_ref(|| Observers {
+        source: None,
+        observers: HashMap::new(),
+    });
+
+    // The stored results only follow the list of queries once the effect below has
+    // run, so any which are missing are read from the client.
+    let current = queries
+        .iter()
+        .map(|query| match results.0.get(query) {
+            Some(result) => result.clone(),
+            None => match &client {
+                Some(client) => QueryResult::current(client, query, &shared_options),
+                None => QueryResult::default(),
+            },
+        })
+        .collect();
+
+    // Only queries which have entered the list are observed, and only those which
+    // have left it stop being observed, so that the fetches of the others continue.
+    use_effect_with_deps(
+        {
+            let observers = observers.clone();
+            let dispatcher = results.dispatcher();
+            move |(client, queries, options): &(Option<QueryClient>, Vec<Q>, OptionsKey)| {
+                let source = client.clone().map(|client| (client, options.clone()));
+                let dropped: Vec<_> = {
+                    let mut observers = observers.borrow_mut();
+                    if observers.source != source {
+                        observers.source = source;
+                        observers.observers.drain().collect()
+                    } else {
+                        let queries: HashSet<&Q> = queries.iter().collect();
+                        let left: Vec<Q> = observers
+                            .observers
+                            .keys()
+                            .filter(|query| !queries.contains(query))
+                            .cloned()
+                            .collect();
+                        left.into_iter()
+                            .filter_map(|query| observers.observers.remove_entry(&query))
+                            .collect()
+                    }
+                };
+                if !dropped.is_empty() {
+                    dispatcher.dispatch(QueryResultsAction::Remove(
+                        dropped.iter().map(|(query, _)| query.clone()).collect(),
+                    ));
+                }
+                // Observers are dropped outside of the borrow, as they interact with the client.
+                drop(dropped);
+                if let Some(client) = client {
+                    for query in queries {
+                        if observers.borrow().observers.contains_key(query) {
+                            continue;
+                        }
+                        let observer = observe_query(client, query, &shared_options, {
+                            let dispatcher = dispatcher.clone();
+                            let query = query.clone();
+                            Callback::from(move |result| {
+                                dispatcher.dispatch(QueryResultsAction::Update(
+                                    query.clone(),
+                                    Box::new(result),
+                                ))
+                            })
+                        });
+                        observers
+                            .borrow_mut()
+                            .observers
+                            .insert(query.clone(), observer);
+                    }
+                }
+                || ()
+            }
+        },
+        (client, queries, OptionsKey(options)),
+    );
+
+    // Every query stops being observed once the component is unmounted.
+    use_effect_with_deps(
+        move |_| {
+            move || {
+                let unmounted = std::mem::take(&mut observers.borrow_mut().observers);
+                drop(unmounted);
+            }
+        },
+        (),
+    );
+
+    current
+}
+
+/// Makes a query for every item of a list, combining their results into a single value.
+///
+/// The results are passed to `combine` in the same order as `queries`.
+///
+/// See the [module-level documentation][self] for more information.
+pub fn use_queries_combined<Q, T>(
+    queries: Vec<Q>,
+    options: Options,
+    combine: impl FnOnce(&[QueryResult<Q>]) -> T,
+) -> T
+where
+    Q: TryQuery + Clone + 'static,
+{
+    combine(&use_queries(queries, options))
+}
@@ -3,10 +3,13 @@
 //! The [`use_query`][] hook (or equivalently, [`use_query_with_options`][]) will
 //! likely be the main way you interact with Yew Query.
 //!
-//! Provided a [`Query`][], `use_query` will fetch the query in the background,
+//! Provided a [`Query`][crate::query::Query] (or [`TryQuery`][]), `use_query` will fetch the query in the background,
 //! returning a [`QueryResult`][] that represents the state of that query as it changes.
+//! The component is re-rendered whenever the query's entry in the [`QueryClient`][] changes,
+//! including when it is updated manually (e.g. by [`QueryClient::set_query_data`][]).
 //! When the query changes, the old data is invalidated and the new data is fetched
-//! automatically (putting the [`QueryResult`][] back into the loading state).
+//! automatically (putting the [`QueryResult`][] back into the loading state). To keep
+//! showing the old data until then, use [`PlaceholderData::KeepPrevious`][].
 //!
 //! ```no_run
 //! use std::future::Future;
@@ -46,8 +49,11 @@
 //! ```
 //!
 use std::ops::Deref;
+use std::rc::Rc;
+use std::time::Duration;
 
-use wasm_bindgen_futures::spawn_local;
+use futures::future::{abortable, AbortHandle};
+use futures::FutureExt;
 use yew::prelude::*;
 
 /// # Notes
@@ -57,105 +63,535 @@ use yew::prelude::*;
 /// none is provided, the query will never be made, and you will be left with just
 /// an empty [`QueryResult`][].
 use crate::components::query_client_provider::use_opt_query_client;
+use crate::components::query_error_boundary::{use_query_error_boundary, QueryError};
 use crate::prelude::QueryClient;
-use crate::query::Query;
-use crate::query_client::Cached;
+use crate::query::{MaybeQuery, TryQuery};
+use crate::query_client::runtime::spawn_local;
+use crate::query_client::{data_fn, Cached, OptionsKey, SharedOptions, Subscription};
 
-/// Options for customizing the behaviour of the query lifecycle.
-///
-/// This gets merged with the options set at the [`QueryClient`][crate::query_client::QueryClient] level.
-#[derive(Clone, Eq, PartialEq, Debug)]
-pub struct Options {
-    /// Whether this query should be attempted at all right now.
-    ///
-    /// Default: `true`
-    pub enabled: bool,
+pub use crate::query_client::{FetchStatus, InitialData, Options, PlaceholderData, QueryStatus};
+
+/// Reflects the state of a query.
+pub struct QueryResult<Q>
+where
+    Q: TryQuery,
+{
+    data: Option<Cached<Q>>,
+    /// The [`stale_time`][Options::stale_time] with which the query is being used.
+    stale_time: Duration,
+    /// Shown while the query has no data of its own. See [`Options::placeholder_data`][].
+    placeholder: Option<Rc<Q::Ok>>,
+    /// Whether the query is being fetched automatically. It is not if it was skipped,
+    /// if it is not [enabled][Options::enabled], or if no client was provided.
+    enabled: bool,
+    /// The client and query which this result reflects, if a client was provided.
+    source: Option<Source<Q>>,
 }
 
-impl Default for Options {
-    fn default() -> Self {
-        Options { enabled: true }
+impl<Q> Clone for QueryResult<Q>
+where
+    Q: TryQuery,
+{
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            stale_time: self.stale_time,
+            placeholder: self.placeholder.clone(),
+            enabled: self.enabled,
+            source: self.source.clone(),
+        }
     }
 }
 
-/// Reflects the state of a query.
-#[derive(Clone)]
-pub struct QueryResult<Q>
+struct Source<Q> {
+    client: QueryClient,
+    query: Rc<Q>,
+    options: SharedOptions,
+}
+
+impl<Q> Clone for Source<Q> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            query: self.query.clone(),
+            options: self.options.clone(),
+        }
+    }
+}
+
+impl<Q> QueryResult<Q>
 where
-    Q: Query,
+    Q: TryQuery + 'static,
 {
-    data: Option<Cached<Q>>,
+    /// Takes a snapshot of the current state of a query in the client.
+    ///
+    /// This is used while rendering, so does not change the client. A query with
+    /// no data yet shows its [initial data][Options::initial_data], which is only
+    /// stored in the cache once the query is observed.
+    pub(crate) fn current(client: &QueryClient, query: &Q, options: &SharedOptions) -> Self
+    where
+        Q: Clone,
+    {
+        let resolved = client.resolve_options::<Q>(&options.borrow());
+        let data = match client.initial_data(query, &options.borrow()) {
+            Some((data, updated_at)) => {
+                Some(Cached::unstored(Rc::new(query.clone()), data, updated_at))
+            }
+            None => client.get_query_data(query),
+        };
+        Self {
+            data,
+            stale_time: resolved.stale_time,
+            placeholder: None,
+            enabled: resolved.enabled,
+            source: Some(Source {
+                client: client.clone(),
+                query: Rc::new(query.clone()),
+                options: options.clone(),
+            }),
+        }
+    }
+
+    /// Creates a callback which acts on the query in the client, or does nothing
+    /// if no client was provided.
+    fn callback<IN>(&self, action: fn(&Source<Q>)) -> Callback<IN> {
+        let source = self.source.clone();
+        Callback::from(move |_| {
+            if let Some(source) = &source {
+                action(source);
+            }
+        })
+    }
+
+    /// Creates a callback which fetches the query again, even if its data is fresh.
+    ///
+    /// ```no_run
+    /// # use std::future::Future;
+    /// # use std::pin::Pin;
+    /// # use yew::prelude::*;
+    /// # use yew_query::prelude::*;
+    /// # #[derive(Clone, Eq, PartialEq, Hash)]
+    /// # struct GetTodos;
+    /// # impl Query for GetTodos {
+    /// #     type Output = Vec<String>;
+    /// #     type Future = Pin<Box<dyn Future<Output = Self::Output>>>;
+    /// #     fn query(&self) -> Self::Future { todo!() }
+    /// # }
+    /// #[function_component(RefreshButton)]
+    /// fn refresh_button() -> Html {
+    ///     let todos = use_query(GetTodos);
+    ///     html! {
+    ///         <button onclick={todos.refetch()} disabled={todos.is_fetching()}>
+    ///             {"Refresh"}
+    ///         </button>
+    ///     }
+    /// }
+    /// ```
+    pub fn refetch<IN>(&self) -> Callback<IN> {
+        self.callback(|source| {
+            let Source {
+                client,
+                query,
+                options,
+            } = source.clone();
+            let options = options.borrow().clone();
+            spawn_local(async move {
+                client.refetch(query, &options).await;
+            });
+        })
+    }
+
+    /// The error to report to the enclosing [`QueryErrorBoundary`][crate::components::query_error_boundary::QueryErrorBoundary],
+    /// if the query has failed and its [`throw_on_error`][Options::throw_on_error] option allows it.
+    pub(crate) fn thrown_error(&self) -> Option<QueryError> {
+        let source = self.source.as_ref()?;
+        let error = self
+            .data
+            .as_ref()
+            .filter(|cached| cached.is_error())?
+            .error_rc()?;
+        let throw_on_error = source
+            .client
+            .resolve_options::<Q>(&source.options.borrow())
+            .throw_on_error;
+        if !throw_on_error.should_throw(error.as_ref()) {
+            return None;
+        }
+        let Source {
+            client,
+            query,
+            options,
+        } = source.clone();
+        Some(QueryError::new(error, move || {
+            client.reset_query_error(query.as_ref());
+            let client = client.clone();
+            let query = query.clone();
+            let options = options.borrow().clone();
+            spawn_local(async move {
+                client.refetch(query, &options).await;
+            });
+        }))
+    }
+
+    /// Creates a callback which invalidates the query, as with [`QueryClient::invalidate_query`][].
+    pub fn invalidate<IN>(&self) -> Callback<IN> {
+        self.callback(|source| source.client.invalidate_query(source.query.as_ref()))
+    }
+
+    /// Creates a callback which removes the query from the cache, as with [`QueryClient::remove_query`][].
+    pub fn remove<IN>(&self) -> Callback<IN> {
+        self.callback(|source| source.client.remove_query(source.query.as_ref()))
+    }
 }
 
 impl<Q> QueryResult<Q>
 where
-    Q: Query,
+    Q: TryQuery,
 {
-    fn new(cached: Cached<Q>) -> Self {
-        Self { data: Some(cached) }
+    /// Whether this is the result of the given query.
+    pub(crate) fn is_for(&self, query: &Q) -> bool {
+        self.source
+            .as_ref()
+            .is_some_and(|source| source.query.as_ref() == query)
+    }
+
+    /// Shows `placeholder` as the data until the query has data of its own.
+    pub(crate) fn with_placeholder(self, placeholder: Option<Rc<Q::Ok>>) -> Self {
+        Self {
+            placeholder,
+            ..self
+        }
+    }
+
+    /// The data shown for the query, which may be [placeholder data][QueryResult::is_placeholder_data].
+    pub fn data(&self) -> Option<&Q::Ok> {
+        self.data
+            .as_ref()
+            .and_then(|cached| cached.data())
+            .or(self.placeholder.as_deref())
+    }
+
+    /// The state of the query in the cache, without any placeholder data.
+    pub(crate) fn cached(&self) -> Option<&Cached<Q>> {
+        self.data.as_ref()
+    }
+
+    pub(crate) fn data_rc(&self) -> Option<Rc<Q::Ok>> {
+        self.data
+            .as_ref()
+            .and_then(|cached| cached.data_rc())
+            .or_else(|| self.placeholder.clone())
+    }
+
+    /// Whether the query has data yet, or has failed.
+    ///
+    /// A query which is [disabled][Options::enabled] remains [pending][QueryStatus::Pending]
+    /// until it is fetched some other way. A query showing placeholder data is
+    /// considered successful.
+    pub fn status(&self) -> QueryStatus {
+        match self.data.as_ref().map(|cached| cached.status()) {
+            None | Some(QueryStatus::Pending) if self.placeholder.is_some() => QueryStatus::Success,
+            status => status.unwrap_or(QueryStatus::Pending),
+        }
+    }
+
+    /// Whether the query is currently being fetched.
+    pub fn fetch_status(&self) -> FetchStatus {
+        self.data
+            .as_ref()
+            .map_or(FetchStatus::Idle, |cached| cached.fetch_status())
+    }
+
+    /// Whether the query is not being fetched automatically, either because it was
+    /// skipped (see [`MaybeQuery`][]) or because it is not [enabled][Options::enabled].
+    ///
+    /// A disabled query may still have data, if it was fetched some other way.
+    pub fn is_disabled(&self) -> bool {
+        !self.enabled
     }
 
-    pub fn data(&self) -> Option<&Q::Output> {
-        self.data.as_ref().and_then(|cached| cached.data())
+    pub fn is_pending(&self) -> bool {
+        self.status() == QueryStatus::Pending
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status() == QueryStatus::Success
+    }
+
+    /// Whether the query is being fetched for the first time, with no data yet.
+    pub fn is_loading(&self) -> bool {
+        self.is_pending() && self.is_fetching()
+    }
+
+    /// Whether the query is being fetched, including background refetches of
+    /// queries which already have data.
+    pub fn is_fetching(&self) -> bool {
+        self.fetch_status() == FetchStatus::Fetching
+    }
+
+    /// Whether the query has no data, or its data is older than its
+    /// [`stale_time`][Options::stale_time] or has been invalidated.
+    pub fn is_stale(&self) -> bool {
+        self.data
+            .as_ref()
+            .is_none_or(|cached| cached.is_stale(self.stale_time))
+    }
+
+    /// Whether [`data`][QueryResult::data] is placeholder data, shown while the
+    /// query has no data of its own.
+    pub fn is_placeholder_data(&self) -> bool {
+        self.placeholder.is_some()
+            && self
+                .data
+                .as_ref()
+                .and_then(|cached| cached.data())
+                .is_none()
+    }
+
+    /// When the data was last fetched, in milliseconds since the Unix epoch.
+    pub fn data_updated_at(&self) -> Option<f64> {
+        self.data.as_ref().and_then(|cached| cached.updated_at())
+    }
+
+    /// The error from the most recent failed fetch, if the query has not succeeded since.
+    pub fn error(&self) -> Option<&Q::Error> {
+        self.data.as_ref().and_then(|cached| cached.error())
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.data.as_ref().is_some_and(|cached| cached.is_error())
+    }
+
+    /// How many times in a row the query has failed, including attempts which
+    /// are being retried.
+    pub fn failure_count(&self) -> usize {
+        self.data
+            .as_ref()
+            .map_or(0, |cached| cached.failure_count())
     }
 }
 
 impl<Q> Default for QueryResult<Q>
 where
-    Q: Query,
+    Q: TryQuery,
 {
     fn default() -> Self {
-        Self { data: None }
+        Self {
+            data: None,
+            stale_time: Duration::MAX,
+            placeholder: None,
+            enabled: false,
+            source: None,
+        }
+    }
+}
+
+/// Keeps a [`QueryResult`][] up to date with the state of a query in the client,
+/// fetching the query if it is enabled.
+///
+/// Stops once dropped, cancelling the fetch if nobody else is waiting on it.
+pub(crate) struct QueryObserver {
+    _subscription: Subscription,
+    abort_handle: Option<AbortHandle>,
+}
+
+impl Drop for QueryObserver {
+    fn drop(&mut self) {
+        if let Some(abort_handle) = &self.abort_handle {
+            abort_handle.abort();
+        }
+    }
+}
+
+/// Starts observing a query, calling `on_change` with its current state immediately
+/// and then whenever it changes.
+pub(crate) fn observe_query<Q>(
+    client: &QueryClient,
+    query: &Q,
+    options: &SharedOptions,
+    on_change: Callback<QueryResult<Q>>,
+) -> QueryObserver
+where
+    Q: TryQuery + Clone + 'static,
+{
+    // The query may have changed, or the cache may have been updated since
+    // the component was rendered.
+    client.seed_initial_data(Rc::new(query.clone()), &options.borrow());
+    let current = QueryResult::current(client, query, options);
+    on_change.emit(current.clone());
+    let subscription = client.subscribe_query_with_shared_options(
+        query.clone(),
+        options.clone(),
+        move |data: Option<Cached<Q>>| {
+            on_change.emit(QueryResult {
+                data,
+                ..current.clone()
+            })
+        },
+    );
+    let mut abort_handle = None;
+    let options = options.borrow().clone();
+    if client.resolve_options::<Q>(&options).enabled {
+        let client = client.clone();
+        let query = query.clone();
+        let (fetch, handle) = abortable(async move {
+            client.fetch_query_with_options(query, &options).await;
+        });
+        abort_handle = Some(handle);
+        spawn_local(fetch.map(|_| ()));
+    }
+    QueryObserver {
+        _subscription: subscription,
+        abort_handle,
     }
 }
 
 /// Makes a query.
 ///
+/// The query may be `None` (see [`MaybeQuery`][]), in which case nothing is fetched,
+/// and the result is [disabled][QueryResult::is_disabled].
+///
 /// See the [module-level documentation][self] for more information.
-pub fn use_query_with_options<Q>(query: Q, options: Options) -> impl Deref<Target = QueryResult<Q>>
+pub fn use_query_with_options<M>(
+    query: M,
+    options: Options,
+) -> impl Deref<Target = QueryResult<M::Query>>
 where
-    Q: Query + Clone + 'static,
+    M: MaybeQuery,
+    M::Query: Clone + 'static,
 {
+    Rc::new(use_query_result(query, options, |_, _| true))
+}
+
+/// Makes a query, only re-rendering when `should_render` returns `true` for the
+/// previous and next results of the query.
+pub(crate) fn use_query_result<M>(
+    query: M,
+    options: Options,
+    should_render: impl Fn(&QueryResult<M::Query>, &QueryResult<M::Query>) -> bool + 'static,
+) -> QueryResult<M::Query>
+where
+    M: MaybeQuery,
+    M::Query: Clone + 'static,
+{
+    let query = query.into_query();
     let client = use_opt_query_client();
-    let query_result = use_state({
+    // The options are replaced on every render, so that the latest closures are used,
+    // while the query is only observed again when the rest of the options change.
+    let shared_options = use_mut_ref(|| options.clone());
+    *shared_options.borrow_mut() = options.clone();
+    // The latest result is kept even when it does not re-render the component, so
+    // that it is shown whenever the component is next rendered.
+    let query_result = use_mut_ref({
         let client = client.clone();
         let query = &query;
-        move || {
-            client
-                .and_then(|client| client.get_query_data(query))
-                .map(QueryResult::new)
-                .unwrap_or_default()
+        let options = &shared_options;
+        move || match (client, query) {
+            (Some(client), Some(query)) => QueryResult::current(&client, query, options)
+                .with_placeholder(placeholder::<M::Query>(
+                    &client,
+                    &options.borrow(),
+                    &QueryResult::default(),
+                )),
+            _ => QueryResult::default(),
         }
     });
+    let rerender = use_state(|| ());
 
-    use_effect_with_deps(
+    // If the query has changed, the state still reflects the previous query until the
+    // effect below has run, so the new query's state is shown in the meantime.
+    let current =
         {
-            let query_result = query_result.clone();
-            move |(client, query, options): &(Option<QueryClient>, Q, Options)| {
-                if options.enabled {
-                    if let Some(client) = client.clone() {
-                        let query = query.clone();
-                        spawn_local(async move {
-                            let cached = client.fetch_query(query).await;
-                            query_result.set(QueryResult::new(cached));
-                        });
-                    }
+            let query_result = query_result.borrow();
+            match (&client, &query) {
+                (Some(client), Some(query)) if !query_result.is_for(query) => {
+                    QueryResult::current(client, query, &shared_options)
+                        .with_placeholder(placeholder(client, &options, &query_result))
                 }
-                || ()
+                (_, None) if query_result.source.is_some() => QueryResult::default(),
+                _ => query_result.clone(),
             }
+        };
+
+    // A failed query may be reported to the enclosing `QueryErrorBoundary`, which
+    // then stops rendering this component.
+    let error_boundary = use_query_error_boundary();
+    use_effect({
+        let thrown = current.thrown_error();
+        move || {
+            if let (Some(error_boundary), Some(error)) = (error_boundary, thrown) {
+                error_boundary.emit(error);
+            }
+            || ()
+        }
+    });
+
+    use_effect_with_deps(
+        move |(client, query, _): &(Option<QueryClient>, Option<M::Query>, OptionsKey)| {
+            // The result shown before the query changed, whose data may be kept as a placeholder.
+            let previous = query_result.borrow().clone();
+            let set = move |result: QueryResult<M::Query>| {
+                let render = should_render(&query_result.borrow(), &result);
+                *query_result.borrow_mut() = result;
+                if render {
+                    rerender.set(());
+                }
+            };
+            let observer = match (client, query) {
+                (Some(client), Some(query)) => Some(observe_query(
+                    client,
+                    query,
+                    &shared_options,
+                    Callback::from({
+                        let client = client.clone();
+                        let options = shared_options.clone();
+                        move |result: QueryResult<M::Query>| {
+                            // The placeholder is only needed until the query has data of its
+                            // own, and is read from the latest options.
+                            let placeholder = match result.data() {
+                                Some(_) => None,
+                                None => placeholder(&client, &options.borrow(), &previous),
+                            };
+                            set(result.with_placeholder(placeholder))
+                        }
+                    }),
+                )),
+                _ => {
+                    // The query may have just been skipped.
+                    set(QueryResult::default());
+                    None
+                }
+            };
+            move || drop(observer)
         },
-        (client, query, options),
+        (client, query, OptionsKey(options)),
     );
 
-    query_result
+    current
+}
+
+/// The placeholder data to show for a query, given the result shown before it changed.
+fn placeholder<Q>(
+    client: &QueryClient,
+    options: &Options,
+    previous: &QueryResult<Q>,
+) -> Option<Rc<Q::Ok>>
+where
+    Q: TryQuery + 'static,
+{
+    match client.resolve_options::<Q>(options).placeholder_data? {
+        PlaceholderData::KeepPrevious => previous.data_rc(),
+        PlaceholderData::With(data) => data_fn::get(&*data, client, "placeholder data"),
+    }
 }
 
 /// Makes a query using the default options.
 ///
 /// See the [module-level documentation][self] for more information.
-pub fn use_query<Q>(query: Q) -> impl Deref<Target = QueryResult<Q>>
+pub fn use_query<M>(query: M) -> impl Deref<Target = QueryResult<M::Query>>
 where
-    Q: Query + Clone + 'static,
+    M: MaybeQuery,
+    M::Query: Clone + 'static,
 {
     use_query_with_options(query, Options::default())
 }
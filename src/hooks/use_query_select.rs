@@ -0,0 +1,189 @@
+//! Yew hook to subscribe to part of a query's data.
+//!
+//! A component which only uses part of a query's data can use [`use_query_select`][]
+//! to pick that part out with a selector. The component is then only re-rendered when
+//! the selected value changes (by [`PartialEq`][]), or when the state of the query
+//! changes (e.g. it starts fetching, or fails), rather than whenever any of the data changes.
+//!
+//! The selector is only run when the query's data changes, so should depend on nothing
+//! but the data.
+//!
+//! ```no_run
+//! use std::future::Future;
+//! use std::pin::Pin;
+//! use yew::prelude::*;
+//! use yew_query::prelude::*;
+//!
+//! struct User {
+//!     name: String,
+//!     friends: Vec<u32>,
+//! }
+//!
+//! #[derive(Clone, Eq, PartialEq, Hash)]
+//! struct GetUser(u32);
+//!
+//! impl Query for GetUser {
+//!     type Output = User;
+//!     type Future = Pin<Box<dyn Future<Output = Self::Output>>>;
+//!
+//!     fn query(&self) -> Self::Future {
+//!         Box::pin(async move {
+//!             todo!("...Making some API calls here...")
+//!         })
+//!     }
+//! }
+//!
+//! #[function_component(UserName)]
+//! fn user_name() -> Html {
+//!     // Not re-rendered when the user's friends change.
+//!     let name = use_query_select(GetUser(1), |user| user.name.clone());
+//!     html! {
+//!         <div>
+//!             if let Some(name) = name.data() {
+//!                 {name}
+//!             } else {
+//!                 {"Loading..."}
+//!             }
+//!         </div>
+//!     }
+//! }
+//! ```
+//!
+use std::ops::Deref;
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use super::use_query::{use_query_result, Options, QueryResult};
+use crate::query::{MaybeQuery, TryQuery};
+
+/// Reflects the state of a query, along with the value selected from its data.
+///
+/// Dereferences to the [`QueryResult`][] of the underlying query, whose
+/// [`data`][QueryResult::data] is the whole of the query's data.
+pub struct UseQuerySelectHandle<Q, T>
+where
+    Q: TryQuery,
+{
+    result: QueryResult<Q>,
+    selected: Option<Rc<T>>,
+}
+
+impl<Q, T> Clone for UseQuerySelectHandle<Q, T>
+where
+    Q: TryQuery,
+{
+    fn clone(&self) -> Self {
+        Self {
+            result: self.result.clone(),
+            selected: self.selected.clone(),
+        }
+    }
+}
+
+impl<Q, T> Deref for UseQuerySelectHandle<Q, T>
+where
+    Q: TryQuery,
+{
+    type Target = QueryResult<Q>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.result
+    }
+}
+
+impl<Q, T> UseQuerySelectHandle<Q, T>
+where
+    Q: TryQuery,
+{
+    /// The value selected from the query's data, which may be selected from
+    /// [placeholder data][QueryResult::is_placeholder_data].
+    pub fn data(&self) -> Option<&T> {
+        self.selected.as_deref()
+    }
+}
+
+/// Selects a value from the data of a query.
+type SelectFn<Q, T> = dyn Fn(&<Q as TryQuery>::Ok) -> T;
+
+/// The most recently selected value, along with the data it was selected from.
+struct Selection<D, T> {
+    data: Option<Rc<D>>,
+    selected: Option<Rc<T>>,
+}
+
+impl<D, T> Selection<D, T> {
+    /// Selects a value from `data`, reusing the previous value if the data is unchanged.
+    fn select(&mut self, data: Option<Rc<D>>, select: impl Fn(&D) -> T) -> Option<Rc<T>> {
+        let unchanged = match (&self.data, &data) {
+            (Some(previous), Some(data)) => Rc::ptr_eq(previous, data),
+            (None, None) => true,
+            _ => false,
+        };
+        if !unchanged {
+            self.selected = data.as_deref().map(|data| Rc::new(select(data)));
+            self.data = data;
+        }
+        self.selected.clone()
+    }
+}
+
+/// Makes a query, selecting a value from its data.
+///
+/// See the [module-level documentation][self] for more information.
+pub fn use_query_select_with_options<M, T>(
+    query: M,
+    options: Options,
+    select: impl Fn(&<M::Query as TryQuery>::Ok) -> T + 'static,
+) -> UseQuerySelectHandle<M::Query, T>
+where
+    M: MaybeQuery,
+    M::Query: Clone + 'static,
+    T: PartialEq + 'static,
+{
+    // The selector is replaced on every render, so that the latest one is used
+    // once the data changes.
+    let select: Rc<SelectFn<M::Query, T>> = Rc::new(select);
+    let select_fn = use_mut_ref(|| select.clone());
+    *select_fn.borrow_mut() = select;
+    let selection = use_mut_ref(|| Selection {
+        data: None,
+        selected: None,
+    });
+
+    let result = use_query_result(query, options, {
+        let select_fn = select_fn.clone();
+        let selection = selection.clone();
+        move |previous: &QueryResult<M::Query>, next: &QueryResult<M::Query>| {
+            let select_fn = select_fn.borrow().clone();
+            let mut selection = selection.borrow_mut();
+            let before = selection.select(previous.data_rc(), &*select_fn);
+            let after = selection.select(next.data_rc(), &*select_fn);
+            before != after
+                || previous.status() != next.status()
+                || previous.fetch_status() != next.fetch_status()
+                || previous.failure_count() != next.failure_count()
+                || previous.is_placeholder_data() != next.is_placeholder_data()
+                || previous.is_disabled() != next.is_disabled()
+        }
+    });
+    let select_fn = select_fn.borrow().clone();
+    let selected = selection.borrow_mut().select(result.data_rc(), &*select_fn);
+
+    UseQuerySelectHandle { result, selected }
+}
+
+/// Makes a query using the default options, selecting a value from its data.
+///
+/// See the [module-level documentation][self] for more information.
+pub fn use_query_select<M, T>(
+    query: M,
+    select: impl Fn(&<M::Query as TryQuery>::Ok) -> T + 'static,
+) -> UseQuerySelectHandle<M::Query, T>
+where
+    M: MaybeQuery,
+    M::Query: Clone + 'static,
+    T: PartialEq + 'static,
+{
+    use_query_select_with_options(query, Options::default(), select)
+}
@@ -5,11 +5,6 @@
 //! Not all the same features are provided, as this project is in very early stages. Expect
 //! possible major changes if planning to use this (particularly before Yew itself is stable).
 //!
-//! Notable features you might expect but are not planned for development any time soon:
-//! *   Automatic retry
-//! *   Stale time/automatic refresh
-//! *   Mutations
-//!
 //! [Yew]: https://yew.rs/
 //! [TanStack Query]: https://tanstack.com/query/v4
 //!
@@ -44,9 +39,17 @@
 //! }
 //! ```
 //!
+//! # Not yet supported
+//!
+//! Yew 0.19 has no `Suspense` support for hooks, so there is no `use_suspense_query`
+//! yet. It is waiting on an upgrade to Yew 0.20 or later; in the meantime, use
+//! [`QueryResult::is_loading`][hooks::use_query::QueryResult::is_loading] to show a
+//! loading state.
+//!
 
 pub mod components;
 pub mod hooks;
+pub mod mutation;
 pub mod query;
 pub mod query_client;
 
@@ -55,9 +58,26 @@ pub mod prelude {
     pub use crate::components::query_client_provider::{
         use_opt_query_client, use_query_client, QueryClientProvider,
     };
+    pub use crate::components::query_error_boundary::{
+        QueryError, QueryErrorBoundary, QueryErrorFallback,
+    };
+    pub use crate::components::query_error_reset_boundary::{
+        use_query_error_reset_boundary, QueryErrorResetBoundary,
+    };
+    pub use crate::hooks::use_infinite_query::{
+        use_infinite_query, use_infinite_query_with_options, UseInfiniteQueryHandle,
+    };
+    pub use crate::hooks::use_mutation::{use_mutation, UseMutationHandle};
+    pub use crate::hooks::use_queries::{use_queries, use_queries_combined};
     pub use crate::hooks::use_query::{
-        use_query, use_query_with_options, Options as QueryOptions, QueryResult,
+        use_query, use_query_with_options, FetchStatus, Options as QueryOptions, QueryResult,
+        QueryStatus,
+    };
+    pub use crate::hooks::use_query_select::{
+        use_query_select, use_query_select_with_options, UseQuerySelectHandle,
     };
+    pub use crate::mutation::Mutation;
+    pub use crate::query::infinite::InfiniteQuery;
+    pub use crate::query::{MaybeQuery, Query, ShareStructure, TryQuery};
     pub use crate::query_client::QueryClient;
-    pub use crate::query::Query;
 }
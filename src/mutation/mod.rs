@@ -0,0 +1,53 @@
+//! Basic mutation functionality.
+//!
+//! Where queries read server-side state, mutations change it. In Yew Query, mutations
+//! are defined as types which implement the [`Mutation`][] trait, and are typically
+//! performed using the [`use_mutation`][crate::hooks::use_mutation::use_mutation] hook.
+//!
+//! Unlike queries, the results of mutations are not cached. Instead, a mutation will
+//! typically update or invalidate the queries it affects once it has completed, using
+//! the [`QueryClient`][] provided to its callbacks.
+
+use crate::query_client::{OptimisticUpdate, QueryClient};
+use std::future::Future;
+
+/// Indicates a type that represents a mutation to be performed.
+pub trait Mutation {
+    /// The input to the mutation, provided each time it is performed.
+    type Variables;
+    /// The result of successfully performing this mutation.
+    type Ok;
+    /// The error produced when this mutation fails.
+    type Error;
+    /// Future type for this mutation.
+    type Future: Future<Output = Result<Self::Ok, Self::Error>>;
+
+    /// Perform the mutation.
+    fn mutate(&self, variables: &Self::Variables) -> Self::Future;
+
+    /// Called before the mutation is performed.
+    ///
+    /// This is a good place to optimistically update any affected queries with the data
+    /// expected once the mutation succeeds. If the mutation fails, the update is rolled
+    /// back before [`on_error`][Mutation::on_error] is called. Otherwise, it is committed
+    /// before [`on_success`][Mutation::on_success] is called.
+    fn on_mutate(&self, _update: &OptimisticUpdate, _variables: &Self::Variables) {}
+
+    /// Called when the mutation succeeds.
+    ///
+    /// This is a good place to update or invalidate any affected queries.
+    fn on_success(&self, _client: &QueryClient, _data: &Self::Ok, _variables: &Self::Variables) {}
+
+    /// Called when the mutation fails.
+    fn on_error(&self, _client: &QueryClient, _error: &Self::Error, _variables: &Self::Variables) {}
+
+    /// Called when the mutation completes, after [`on_success`][Mutation::on_success]
+    /// or [`on_error`][Mutation::on_error], whether it succeeded or not.
+    fn on_settled(
+        &self,
+        _client: &QueryClient,
+        _result: Result<&Self::Ok, &Self::Error>,
+        _variables: &Self::Variables,
+    ) {
+    }
+}
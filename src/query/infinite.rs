@@ -0,0 +1,195 @@
+//! Queries which are loaded a page at a time.
+//!
+//! An [`InfiniteQuery`][] describes how to fetch a single page of data, and how to
+//! determine the parameters for the pages before and after a given page. All of the
+//! loaded pages are stored together, as [`InfiniteData`][], in a single entry of the
+//! [`QueryClient`][crate::query_client::QueryClient] under the key [`Infinite`][].
+//!
+//! Infinite queries are typically used with the
+//! [`use_infinite_query`][crate::hooks::use_infinite_query::use_infinite_query] hook.
+
+use super::TryQuery;
+use futures::future::LocalBoxFuture;
+use futures::FutureExt;
+use std::future::Future;
+use std::hash::Hash;
+use std::rc::Rc;
+
+/// Indicates a type that represents a query which is loaded a page at a time.
+pub trait InfiniteQuery: Hash + Eq + PartialEq {
+    /// Identifies a page to be fetched, such as a page number or a cursor.
+    type PageParam: Clone;
+    /// The result of successfully fetching a single page.
+    type Ok;
+    /// The error produced when fetching a page fails.
+    type Error;
+    /// Future type for fetching a page.
+    type Future: Future<Output = Result<Self::Ok, Self::Error>>;
+
+    /// The parameter for the first page to be fetched.
+    fn initial_page_param(&self) -> Self::PageParam;
+
+    /// Attempt to fetch a single page.
+    fn query_page(&self, page_param: &Self::PageParam) -> Self::Future;
+
+    /// The parameter for the page after the last loaded page, or `None` if there
+    /// are no more pages.
+    fn next_page_param(&self, last_page: &Self::Ok) -> Option<Self::PageParam>;
+
+    /// The parameter for the page before the first loaded page, or `None` if there
+    /// are no previous pages.
+    ///
+    /// By default, pages are only loaded forwards, so there are no previous pages.
+    fn previous_page_param(&self, _first_page: &Self::Ok) -> Option<Self::PageParam> {
+        None
+    }
+}
+
+/// The pages loaded for an [`InfiniteQuery`][], in order.
+pub struct InfiniteData<I>
+where
+    I: InfiniteQuery,
+{
+    /// The loaded pages.
+    pub pages: Vec<Rc<I::Ok>>,
+    /// The parameters with which each of the loaded pages was fetched.
+    pub page_params: Vec<I::PageParam>,
+}
+
+impl<I> Clone for InfiniteData<I>
+where
+    I: InfiniteQuery,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pages: self.pages.clone(),
+            page_params: self.page_params.clone(),
+        }
+    }
+}
+
+impl<I> InfiniteData<I>
+where
+    I: InfiniteQuery,
+{
+    fn with_next_page(&self, page: I::Ok, page_param: I::PageParam) -> Self {
+        let mut data = self.clone();
+        data.pages.push(Rc::new(page));
+        data.page_params.push(page_param);
+        data
+    }
+
+    fn with_previous_page(&self, page: I::Ok, page_param: I::PageParam) -> Self {
+        let mut data = self.clone();
+        data.pages.insert(0, Rc::new(page));
+        data.page_params.insert(0, page_param);
+        data
+    }
+
+    /// The parameter for the page after the last loaded page, if there is one.
+    pub fn next_page_param(&self, query: &I) -> Option<I::PageParam> {
+        query.next_page_param(self.pages.last()?)
+    }
+
+    /// The parameter for the page before the first loaded page, if there is one.
+    pub fn previous_page_param(&self, query: &I) -> Option<I::PageParam> {
+        query.previous_page_param(self.pages.first()?)
+    }
+}
+
+/// The key under which the pages of an [`InfiniteQuery`][] are cached.
+///
+/// Fetching this query loads the first page. When it is refetched, all of the pages
+/// which were previously loaded are fetched again, in order.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Infinite<I>(pub I);
+
+/// Which page of an [`InfiniteQuery`][] to fetch next.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub(crate) enum Direction {
+    Next,
+    Previous,
+}
+
+impl<I> Infinite<I>
+where
+    I: InfiniteQuery + Clone + 'static,
+{
+    /// The parameter for the page before or after those in `previous`, if there is one.
+    pub(crate) fn page_param(
+        &self,
+        previous: &InfiniteData<I>,
+        direction: Direction,
+    ) -> Option<I::PageParam> {
+        match direction {
+            Direction::Next => previous.next_page_param(&self.0),
+            Direction::Previous => previous.previous_page_param(&self.0),
+        }
+    }
+
+    /// Fetches one more page, adding it before or after those in `previous`.
+    pub(crate) fn fetch_page(
+        &self,
+        previous: Rc<InfiniteData<I>>,
+        page_param: I::PageParam,
+        direction: Direction,
+    ) -> LocalBoxFuture<'static, Result<InfiniteData<I>, I::Error>> {
+        let page = self.0.query_page(&page_param);
+        async move {
+            let page = page.await?;
+            Ok(match direction {
+                Direction::Next => previous.with_next_page(page, page_param),
+                Direction::Previous => previous.with_previous_page(page, page_param),
+            })
+        }
+        .boxed_local()
+    }
+}
+
+impl<I> TryQuery for Infinite<I>
+where
+    I: InfiniteQuery + Clone + 'static,
+{
+    type Ok = InfiniteData<I>;
+    type Error = I::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Ok, Self::Error>>;
+
+    fn try_query(&self) -> Self::Future {
+        let page_param = self.0.initial_page_param();
+        let page = self.0.query_page(&page_param);
+        async move {
+            Ok(InfiniteData {
+                pages: vec![Rc::new(page.await?)],
+                page_params: vec![page_param],
+            })
+        }
+        .boxed_local()
+    }
+
+    fn try_refetch(&self, previous: Rc<Self::Ok>) -> Self::Future {
+        let query = self.0.clone();
+        let page_count = previous.pages.len();
+        let page_param = previous
+            .page_params
+            .first()
+            .cloned()
+            .unwrap_or_else(|| query.initial_page_param());
+        async move {
+            let mut data = InfiniteData {
+                pages: Vec::with_capacity(page_count),
+                page_params: Vec::with_capacity(page_count),
+            };
+            let mut page_param = Some(page_param);
+            while let Some(param) = page_param.take() {
+                let page = query.query_page(&param).await?;
+                if data.pages.len() + 1 < page_count {
+                    page_param = query.next_page_param(&page);
+                }
+                data.pages.push(Rc::new(page));
+                data.page_params.push(param);
+            }
+            Ok(data)
+        }
+        .boxed_local()
+    }
+}
@@ -12,17 +12,32 @@
 //! assume that they are intended to fetch the same data, and deduplicate requests
 //! accordingly.
 //!
+//! Queries which may fail should instead implement [`TryQuery`][], which separates
+//! the successful output from the error. Errors are tracked by the [`QueryClient`][]
+//! separately from the data, so a failed fetch does not replace previously fetched
+//! data, and is considered stale so that it will be attempted again. Every [`Query`][]
+//! is also a [`TryQuery`][] which never fails.
+//!
 //! [`QueryClient`]: crate::query_client::QueryClient
 
+use futures::future::NeverError;
+use futures::FutureExt;
+use std::convert::Infallible;
 use std::future::Future;
 use std::hash::Hash;
+use std::rc::Rc;
+
+pub mod infinite;
+mod structural_sharing;
+
+pub use structural_sharing::{share_if_equal, ShareStructure};
 
 /// Indicates a type that represents a query to be made.
 pub trait Query: Hash + Eq + PartialEq {
     /// The result of performing this query.
     ///
-    /// Yew Query makes no assumptions about error handling. If your query may
-    /// fail, this type should likely be a [`Result`][].
+    /// If your query may fail, implement [`TryQuery`][] instead, so that errors
+    /// are not cached as if they were valid data.
     type Output;
     /// Future type for this query.
     type Future: Future<Output = Self::Output>;
@@ -32,4 +47,193 @@ pub trait Query: Hash + Eq + PartialEq {
     /// Queries may be performed multiple times from the same query object. The
     /// query client will determine when query data is invalid and refetch accordingly.
     fn query(&self) -> Self::Future;
+
+    /// Combine refetched data with the data previously cached for this query.
+    ///
+    /// By default, the refetched data replaces the previous data, even if it is equal.
+    /// Override this to keep the previous data when it is unchanged, using
+    /// [`share_if_equal`][] or [`ShareStructure`][], so that observers can tell nothing
+    /// changed using [`Rc::ptr_eq`][]:
+    ///
+    /// ```no_run
+    /// # use std::future::Future;
+    /// # use std::pin::Pin;
+    /// # use std::rc::Rc;
+    /// # use yew_query::prelude::*;
+    /// # #[derive(PartialEq)]
+    /// # struct Todo;
+    /// # impl ShareStructure for Todo {
+    /// #     fn share_structure(previous: &Rc<Self>, next: Rc<Self>) -> Rc<Self> {
+    /// #         yew_query::query::share_if_equal(previous, next)
+    /// #     }
+    /// # }
+    /// #[derive(Clone, Eq, PartialEq, Hash)]
+    /// struct GetTodos;
+    ///
+    /// impl Query for GetTodos {
+    ///     type Output = Vec<Rc<Todo>>;
+    ///     type Future = Pin<Box<dyn Future<Output = Self::Output>>>;
+    ///
+    ///     fn query(&self) -> Self::Future {
+    ///         Box::pin(async move {
+    ///             todo!("...Making some API calls here...")
+    ///         })
+    ///     }
+    ///
+    ///     fn share_structure(
+    ///         &self,
+    ///         previous: &Rc<Self::Output>,
+    ///         next: Rc<Self::Output>,
+    ///     ) -> Rc<Self::Output> {
+    ///         ShareStructure::share_structure(previous, next)
+    ///     }
+    /// }
+    /// ```
+    fn share_structure(
+        &self,
+        _previous: &Rc<Self::Output>,
+        next: Rc<Self::Output>,
+    ) -> Rc<Self::Output> {
+        next
+    }
+}
+
+/// Indicates a type that represents a query to be made, which may fail.
+///
+/// ```no_run
+/// use std::future::Future;
+/// use std::pin::Pin;
+/// use yew_query::prelude::*;
+///
+/// struct User;
+/// struct ApiError;
+///
+/// #[derive(Clone, Eq, PartialEq, Hash)]
+/// struct GetUser(u32);
+///
+/// impl TryQuery for GetUser {
+///     type Ok = User;
+///     type Error = ApiError;
+///     type Future = Pin<Box<dyn Future<Output = Result<User, ApiError>>>>;
+///
+///     fn try_query(&self) -> Self::Future {
+///         Box::pin(async move {
+///             todo!("...Making some API calls here...")
+///         })
+///     }
+/// }
+/// ```
+pub trait TryQuery: Hash + Eq + PartialEq {
+    /// The result of successfully performing this query.
+    type Ok;
+    /// The error produced when this query fails.
+    type Error;
+    /// Future type for this query.
+    type Future: Future<Output = Result<Self::Ok, Self::Error>>;
+
+    /// Attempt to perform the query.
+    ///
+    /// When the query fails, previously fetched data remains available alongside
+    /// the error, and the query will be attempted again the next time it is fetched.
+    fn try_query(&self) -> Self::Future;
+
+    /// Attempt to perform the query again, when data has previously been fetched.
+    ///
+    /// By default, this is the same as [`try_query`][TryQuery::try_query]. Queries whose
+    /// data is built up over time (such as [`InfiniteQuery`][infinite::InfiniteQuery])
+    /// may use the previous data to determine what to fetch.
+    fn try_refetch(&self, _previous: Rc<Self::Ok>) -> Self::Future {
+        self.try_query()
+    }
+
+    /// Combine refetched data with the data previously cached for this query.
+    ///
+    /// See [`Query::share_structure`][].
+    fn share_structure(&self, _previous: &Rc<Self::Ok>, next: Rc<Self::Ok>) -> Rc<Self::Ok> {
+        next
+    }
+}
+
+impl<Q> TryQuery for Q
+where
+    Q: Query,
+{
+    type Ok = Q::Output;
+    type Error = Infallible;
+    type Future = NeverError<Q::Future>;
+
+    fn try_query(&self) -> Self::Future {
+        self.query().never_error()
+    }
+
+    fn share_structure(&self, previous: &Rc<Self::Ok>, next: Rc<Self::Ok>) -> Rc<Self::Ok> {
+        Query::share_structure(self, previous, next)
+    }
+}
+
+/// A query which may not be ready to be made yet, such as one which depends on the
+/// data of another query.
+///
+/// This is implemented for every [`TryQuery`][], and for `Option`s of them, where
+/// `None` means the query is skipped. This allows [`use_query`][crate::hooks::use_query::use_query]
+/// to be given `None` until the inputs of the query are available:
+///
+/// ```no_run
+/// # use std::future::Future;
+/// # use std::pin::Pin;
+/// # use yew::prelude::*;
+/// # use yew_query::prelude::*;
+/// # struct User { id: u32 }
+/// # #[derive(Clone, Eq, PartialEq, Hash)]
+/// # struct GetCurrentUser;
+/// # impl Query for GetCurrentUser {
+/// #     type Output = User;
+/// #     type Future = Pin<Box<dyn Future<Output = Self::Output>>>;
+/// #     fn query(&self) -> Self::Future { todo!() }
+/// # }
+/// # #[derive(Clone, Eq, PartialEq, Hash)]
+/// # struct GetProjects { user_id: u32 }
+/// # impl Query for GetProjects {
+/// #     type Output = Vec<String>;
+/// #     type Future = Pin<Box<dyn Future<Output = Self::Output>>>;
+/// #     fn query(&self) -> Self::Future { todo!() }
+/// # }
+/// #[function_component(Projects)]
+/// fn projects() -> Html {
+///     let user = use_query(GetCurrentUser);
+///     let projects = use_query(user.data().map(|user| GetProjects { user_id: user.id }));
+///     html! {
+///         if let Some(projects) = projects.data() {
+///             {format!("You have {} projects", projects.len())}
+///         }
+///     }
+/// }
+/// ```
+pub trait MaybeQuery {
+    type Query: TryQuery;
+
+    /// The query to be made, or `None` if it should be skipped.
+    fn into_query(self) -> Option<Self::Query>;
+}
+
+impl<Q> MaybeQuery for Q
+where
+    Q: TryQuery,
+{
+    type Query = Q;
+
+    fn into_query(self) -> Option<Q> {
+        Some(self)
+    }
+}
+
+impl<Q> MaybeQuery for Option<Q>
+where
+    Q: TryQuery,
+{
+    type Query = Q;
+
+    fn into_query(self) -> Option<Q> {
+        self
+    }
 }
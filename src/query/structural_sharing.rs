@@ -0,0 +1,96 @@
+use std::rc::Rc;
+
+/// Data which can reuse the unchanged parts of its previous value when it is refetched.
+///
+/// Queries opt in to structural sharing by overriding [`Query::share_structure`][super::Query::share_structure]
+/// (or [`TryQuery::share_structure`][super::TryQuery::share_structure]). When the refetched
+/// data is unchanged, the previously cached [`Rc`][] is kept, so observers can tell that
+/// nothing changed using [`Rc::ptr_eq`][].
+///
+/// This is implemented for `Vec<Rc<T>>` and `Option<Rc<T>>`, reusing each item that is
+/// unchanged. Other types may implement it using [`share_if_equal`][], or by sharing their
+/// fields:
+///
+/// ```
+/// use std::rc::Rc;
+/// use yew_query::query::{share_if_equal, ShareStructure};
+///
+/// #[derive(PartialEq)]
+/// struct Todo {
+///     title: String,
+///     done: bool,
+/// }
+///
+/// impl ShareStructure for Todo {
+///     fn share_structure(previous: &Rc<Self>, next: Rc<Self>) -> Rc<Self> {
+///         share_if_equal(previous, next)
+///     }
+/// }
+///
+/// let previous = Rc::new(vec![
+///     Rc::new(Todo { title: "Write docs".into(), done: false }),
+///     Rc::new(Todo { title: "Release".into(), done: false }),
+/// ]);
+/// let next = Rc::new(vec![
+///     Rc::new(Todo { title: "Write docs".into(), done: true }),
+///     Rc::new(Todo { title: "Release".into(), done: false }),
+/// ]);
+/// let shared = ShareStructure::share_structure(&previous, next);
+/// assert!(!Rc::ptr_eq(&shared, &previous));
+/// assert!(!Rc::ptr_eq(&shared[0], &previous[0]));
+/// assert!(Rc::ptr_eq(&shared[1], &previous[1]));
+/// ```
+pub trait ShareStructure {
+    /// Returns `next`, reusing as much of `previous` as is unchanged. If nothing has
+    /// changed, `previous` itself is returned.
+    fn share_structure(previous: &Rc<Self>, next: Rc<Self>) -> Rc<Self>;
+}
+
+/// Returns `previous` if it is equal to `next`, and `next` otherwise.
+pub fn share_if_equal<T: PartialEq + ?Sized>(previous: &Rc<T>, next: Rc<T>) -> Rc<T> {
+    if Rc::ptr_eq(previous, &next) || previous == &next {
+        previous.clone()
+    } else {
+        next
+    }
+}
+
+impl<T: ShareStructure> ShareStructure for Vec<Rc<T>> {
+    fn share_structure(previous: &Rc<Self>, next: Rc<Self>) -> Rc<Self> {
+        let items: Vec<_> = next
+            .iter()
+            .enumerate()
+            .map(|(index, item)| match previous.get(index) {
+                Some(previous) => T::share_structure(previous, item.clone()),
+                None => item.clone(),
+            })
+            .collect();
+        let unchanged = items.len() == previous.len()
+            && items
+                .iter()
+                .zip(previous.iter())
+                .all(|(item, previous)| Rc::ptr_eq(item, previous));
+        if unchanged {
+            previous.clone()
+        } else {
+            Rc::new(items)
+        }
+    }
+}
+
+impl<T: ShareStructure> ShareStructure for Option<Rc<T>> {
+    fn share_structure(previous: &Rc<Self>, next: Rc<Self>) -> Rc<Self> {
+        match (previous.as_ref(), next.as_ref()) {
+            (Some(previous_item), Some(item)) => {
+                let item = T::share_structure(previous_item, item.clone());
+                if Rc::ptr_eq(&item, previous_item) {
+                    previous.clone()
+                } else {
+                    Rc::new(Some(item))
+                }
+            }
+            (None, None) => previous.clone(),
+            _ => next,
+        }
+    }
+}
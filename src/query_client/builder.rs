@@ -0,0 +1,143 @@
+use super::{
+    ClientInternals, FocusManager, NavigatorOnlineManager, OnlineManager, Options, PlaceholderData,
+    QueryClient, Retry, RetryDelay, ThrowOnError, WindowFocusManager,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Builds a [`QueryClient`][] with default options for all of its queries.
+///
+/// Options passed to individual hooks take precedence over these defaults.
+///
+/// ```
+/// use std::time::Duration;
+/// use yew_query::query_client::{QueryClient, Retry};
+///
+/// let client = QueryClient::builder()
+///     .stale_time(Duration::from_secs(30))
+///     .retry(Retry::Count(1))
+///     .build();
+/// ```
+#[derive(Clone, Default)]
+pub struct QueryClientBuilder {
+    default_options: Options,
+    focus_manager: Option<Rc<dyn FocusManager>>,
+    online_manager: Option<Rc<dyn OnlineManager>>,
+}
+
+impl QueryClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set all of the default options at once, replacing any set previously.
+    ///
+    /// [`placeholder_data`][Options::placeholder_data] other than [`PlaceholderData::KeepPrevious`][]
+    /// and [`initial_data`][Options::initial_data] are ignored (with a warning in debug
+    /// builds) by queries of any other type than the one they were created for.
+    pub fn default_options(mut self, options: Options) -> Self {
+        self.default_options = options;
+        self
+    }
+
+    /// See [`Options::enabled`][].
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.default_options.enabled = Some(enabled);
+        self
+    }
+
+    /// See [`Options::stale_time`][].
+    pub fn stale_time(mut self, stale_time: Duration) -> Self {
+        self.default_options.stale_time = Some(stale_time);
+        self
+    }
+
+    /// See [`Options::gc_time`][].
+    pub fn gc_time(mut self, gc_time: Duration) -> Self {
+        self.default_options.gc_time = Some(gc_time);
+        self
+    }
+
+    /// See [`Options::retry`][].
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.default_options.retry = Some(retry);
+        self
+    }
+
+    /// See [`Options::retry_delay`][].
+    pub fn retry_delay(mut self, retry_delay: RetryDelay) -> Self {
+        self.default_options.retry_delay = Some(retry_delay);
+        self
+    }
+
+    /// See [`Options::refetch_interval`][].
+    pub fn refetch_interval(mut self, refetch_interval: Duration) -> Self {
+        self.default_options.refetch_interval = Some(refetch_interval);
+        self
+    }
+
+    /// See [`Options::refetch_interval_in_background`][].
+    pub fn refetch_interval_in_background(mut self, refetch_interval_in_background: bool) -> Self {
+        self.default_options.refetch_interval_in_background = Some(refetch_interval_in_background);
+        self
+    }
+
+    /// See [`Options::refetch_on_window_focus`][].
+    pub fn refetch_on_window_focus(mut self, refetch_on_window_focus: bool) -> Self {
+        self.default_options.refetch_on_window_focus = Some(refetch_on_window_focus);
+        self
+    }
+
+    /// See [`Options::refetch_on_reconnect`][].
+    pub fn refetch_on_reconnect(mut self, refetch_on_reconnect: bool) -> Self {
+        self.default_options.refetch_on_reconnect = Some(refetch_on_reconnect);
+        self
+    }
+
+    /// Whether every query keeps showing the data of the previous query while a new
+    /// one is loading, as with [`PlaceholderData::KeepPrevious`][].
+    ///
+    /// Other placeholder data, and [initial data][Options::initial_data], is only
+    /// valid for queries of one type, so is set using [`QueryClient::set_query_defaults`][].
+    pub fn keep_previous_data(mut self, keep_previous_data: bool) -> Self {
+        self.default_options.placeholder_data =
+            keep_previous_data.then_some(PlaceholderData::KeepPrevious);
+        self
+    }
+
+    /// See [`Options::throw_on_error`][].
+    pub fn throw_on_error(mut self, throw_on_error: ThrowOnError) -> Self {
+        self.default_options.throw_on_error = Some(throw_on_error);
+        self
+    }
+
+    /// Set how the client tracks whether the app is focused.
+    ///
+    /// Default: [`WindowFocusManager`][]
+    pub fn focus_manager(mut self, focus_manager: impl FocusManager + 'static) -> Self {
+        self.focus_manager = Some(Rc::new(focus_manager));
+        self
+    }
+
+    /// Set how the client tracks whether the app is online.
+    ///
+    /// Default: [`NavigatorOnlineManager`][]
+    pub fn online_manager(mut self, online_manager: impl OnlineManager + 'static) -> Self {
+        self.online_manager = Some(Rc::new(online_manager));
+        self
+    }
+
+    pub fn build(self) -> QueryClient {
+        QueryClient(Rc::new(RefCell::new(ClientInternals {
+            cache: Default::default(),
+            default_options: self.default_options,
+            focus_manager: self
+                .focus_manager
+                .unwrap_or_else(|| Rc::new(WindowFocusManager::new())),
+            online_manager: self
+                .online_manager
+                .unwrap_or_else(|| Rc::new(NavigatorOnlineManager::new())),
+        })))
+    }
+}
@@ -1,23 +1,91 @@
-use super::State;
-use crate::query::Query;
+use super::{Cached, State};
+use crate::query::TryQuery;
+use crate::query_client::polling::Polling;
+use crate::query_client::{clock, Options, SharedOptions};
 use std::collections::HashMap;
 use std::rc::Rc;
+use yew::Callback;
 
 #[derive(Debug)]
 pub(super) struct Bucket<Q>
 where
-    Q: Query,
+    Q: TryQuery,
 {
-    pub values: HashMap<Rc<Q>, State<Q::Output>>,
+    pub values: HashMap<Rc<Q>, State<Q::Ok, Q::Error>>,
+    pub observers: HashMap<Rc<Q>, Observers<Q>>,
+    /// Entries which have been optimistically updated by in-progress mutations,
+    /// along with the number of such mutations.
+    pub holds: HashMap<Rc<Q>, usize>,
+    /// Options for every query of this type, set by [`QueryClient::set_query_defaults`][crate::query_client::QueryClient::set_query_defaults].
+    pub defaults: Options,
 }
 
 impl<Q> Default for Bucket<Q>
 where
-    Q: Query,
+    Q: TryQuery,
 {
     fn default() -> Self {
         Self {
             values: HashMap::with_capacity(1),
+            observers: HashMap::with_capacity(1),
+            holds: HashMap::new(),
+            defaults: Options::default(),
         }
     }
 }
+
+impl<Q> Bucket<Q>
+where
+    Q: TryQuery,
+{
+    /// Restarts the garbage collection countdown of an unobserved entry, as it
+    /// has just been written to.
+    pub fn touch(&mut self, query: Rc<Q>) {
+        let observers = self.observers.entry(query).or_default();
+        if observers.subscribers.is_empty() {
+            observers.idle_since = clock::now();
+        }
+    }
+}
+
+/// Tracks the observers (e.g. mounted hooks) of a cache entry.
+///
+/// Kept separately from the values, so that a mounted hook remains an
+/// observer even if its entry is removed and later refetched.
+#[derive(Debug)]
+pub(super) struct Observers<Q>
+where
+    Q: TryQuery,
+{
+    pub subscribers: Vec<Subscriber<Q>>,
+    /// When the entry last became unobserved (or was last written while unobserved).
+    pub idle_since: f64,
+    /// Refetches the entry on the shortest interval requested by its subscribers.
+    pub polling: Option<Polling>,
+}
+
+impl<Q> Default for Observers<Q>
+where
+    Q: TryQuery,
+{
+    fn default() -> Self {
+        Self {
+            subscribers: vec![],
+            idle_since: clock::now(),
+            polling: None,
+        }
+    }
+}
+
+/// Is notified whenever the state of a cache entry changes.
+#[derive(Debug)]
+pub(crate) struct Subscriber<Q>
+where
+    Q: TryQuery,
+{
+    pub id: usize,
+    pub callback: Callback<Option<Cached<Q>>>,
+    /// The options with which the subscriber is using the query, read whenever
+    /// the query is refetched on its behalf.
+    pub options: SharedOptions,
+}
@@ -1,19 +1,46 @@
 use super::State;
-use crate::query::Query;
+use crate::query::infinite::Direction;
+use crate::query::TryQuery;
+use crate::query_client::request::Request;
 use std::rc::Rc;
+use std::time::Duration;
+
+/// Whether a query has data, as reported by [`Cached::status`][].
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub enum QueryStatus {
+    /// The query has neither data nor an error yet.
+    Pending,
+    /// The query has data. It may still be fetching, or its data may be stale.
+    Success,
+    /// The most recent fetch failed. Any previously fetched data is still available.
+    Error,
+}
+
+/// Whether a query is being fetched, as reported by [`Cached::fetch_status`][].
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub enum FetchStatus {
+    /// The query is being fetched.
+    Fetching,
+    /// The query is waiting for the network to come back before it continues fetching.
+    ///
+    /// See [`OnlineManager`][crate::query_client::OnlineManager].
+    Paused,
+    /// The query is not being fetched.
+    Idle,
+}
 
 #[derive(Debug)]
 pub struct Cached<Q>
 where
-    Q: Query,
+    Q: TryQuery,
 {
-    pub(super) query: Rc<Q>,
-    pub(super) data: State<Q::Output>,
+    pub(crate) query: Rc<Q>,
+    pub(super) data: State<Q::Ok, Q::Error>,
 }
 
 impl<Q> Clone for Cached<Q>
 where
-    Q: Query,
+    Q: TryQuery,
 {
     fn clone(&self) -> Self {
         Self {
@@ -25,13 +52,48 @@ where
 
 impl<Q> Cached<Q>
 where
-    Q: Query,
+    Q: TryQuery,
 {
+    /// An entry for a query which has no data, such as one which has been removed.
+    pub(crate) fn empty(query: Rc<Q>) -> Self {
+        Self {
+            query,
+            data: State::default(),
+        }
+    }
+
+    /// An entry for a query which has not been stored in the cache yet, showing
+    /// data which was last updated at `updated_at`.
+    pub(crate) fn unstored(query: Rc<Q>, data: Rc<Q::Ok>, updated_at: f64) -> Self {
+        Self {
+            query,
+            data: State::valid_at(data, updated_at),
+        }
+    }
+
+    /// Whether this entry is waiting on the given request.
+    pub(crate) fn is_pending(&self, request: &Request<Q::Ok, Q::Error>) -> bool {
+        matches!(self.data.pending_data(), Some(pending) if pending.ptr_eq(request))
+    }
+
+    pub(crate) fn pending_request(&self) -> Option<Request<Q::Ok, Q::Error>> {
+        self.data.pending_data()
+    }
+
+    /// The page being fetched, if another page of an infinite query is being fetched.
+    pub(crate) fn fetching_page(&self) -> Option<Direction> {
+        self.data.pending_data().and_then(|request| request.page())
+    }
+
+    pub(crate) fn data_rc(&self) -> Option<Rc<Q::Ok>> {
+        self.data.data_rc()
+    }
+
     pub fn query(&self) -> &Q {
         self.query.as_ref()
     }
 
-    pub fn data(&self) -> Option<&Q::Output> {
+    pub fn data(&self) -> Option<&Q::Ok> {
         self.data.data()
     }
 
@@ -43,7 +105,43 @@ where
         self.data.is_valid()
     }
 
+    /// The error from the most recent failed fetch, if the query has not succeeded since.
+    pub fn error(&self) -> Option<&Q::Error> {
+        self.data.error()
+    }
+
+    pub(crate) fn error_rc(&self) -> Option<Rc<Q::Error>> {
+        self.data.error_rc()
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.data.is_error()
+    }
+
+    /// How many times in a row the query has failed, including attempts which
+    /// are being retried.
+    pub fn failure_count(&self) -> usize {
+        self.data.failure_count()
+    }
+
     pub fn is_idle(&self) -> bool {
         self.data.is_idle()
     }
+
+    /// When the data was last fetched, in milliseconds since the Unix epoch.
+    pub fn updated_at(&self) -> Option<f64> {
+        self.data.updated_at()
+    }
+
+    pub fn is_stale(&self, stale_time: Duration) -> bool {
+        self.data.is_stale(stale_time)
+    }
+
+    pub fn status(&self) -> QueryStatus {
+        self.data.status()
+    }
+
+    pub fn fetch_status(&self) -> FetchStatus {
+        self.data.fetch_status()
+    }
 }
@@ -1,5 +1,5 @@
 use super::State;
-use crate::query::Query;
+use crate::query::TryQuery;
 use std::collections::hash_map::{
     Entry as MapEntry, OccupiedEntry as OccupiedMapEntry, VacantEntry as VacantMapEntry,
 };
@@ -7,25 +7,25 @@ use std::rc::Rc;
 
 pub(crate) enum Entry<'cache, Q>
 where
-    Q: Query,
+    Q: TryQuery,
 {
     Occupied(OccupiedEntry<'cache, Q>),
     Vacant(VacantEntry<'cache, Q>),
 }
 
-pub(crate) struct OccupiedEntry<'cache, Q>(OccupiedMapEntry<'cache, Rc<Q>, State<Q::Output>>)
+pub(crate) struct OccupiedEntry<'cache, Q>(OccupiedMapEntry<'cache, Rc<Q>, State<Q::Ok, Q::Error>>)
 where
-    Q: Query;
+    Q: TryQuery;
 
-pub(crate) struct VacantEntry<'cache, Q>(VacantMapEntry<'cache, Rc<Q>, State<Q::Output>>)
+pub(crate) struct VacantEntry<'cache, Q>(VacantMapEntry<'cache, Rc<Q>, State<Q::Ok, Q::Error>>)
 where
-    Q: Query;
+    Q: TryQuery;
 
 impl<'cache, Q> Entry<'cache, Q>
 where
-    Q: Query,
+    Q: TryQuery,
 {
-    pub fn or_default(self) -> &'cache mut State<Q::Output> {
+    pub fn or_default(self) -> &'cache mut State<Q::Ok, Q::Error> {
         match self {
             Entry::Occupied(entry) => entry.0.into_mut(),
             Entry::Vacant(entry) => entry.0.insert(Default::default()),
@@ -33,11 +33,11 @@ where
     }
 }
 
-impl<'cache, Q> From<MapEntry<'cache, Rc<Q>, State<Q::Output>>> for Entry<'cache, Q>
+impl<'cache, Q> From<MapEntry<'cache, Rc<Q>, State<Q::Ok, Q::Error>>> for Entry<'cache, Q>
 where
-    Q: Query,
+    Q: TryQuery,
 {
-    fn from(entry: MapEntry<'cache, Rc<Q>, State<Q::Output>>) -> Self {
+    fn from(entry: MapEntry<'cache, Rc<Q>, State<Q::Ok, Q::Error>>) -> Self {
         match entry {
             MapEntry::Occupied(entry) => Self::Occupied(OccupiedEntry(entry)),
             MapEntry::Vacant(entry) => Self::Vacant(VacantEntry(entry)),
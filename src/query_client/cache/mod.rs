@@ -1,8 +1,12 @@
-use crate::query::Query;
+use crate::query::TryQuery;
+use crate::query_client::polling::Polling;
+use crate::query_client::{clock, Options, SharedOptions};
 use std::borrow::Borrow;
 use std::hash::Hash;
 use std::rc::Rc;
+use std::time::Duration;
 use type_map::TypeMap;
+use yew::Callback;
 
 mod bucket;
 mod cached;
@@ -10,39 +14,275 @@ mod entry;
 mod state;
 
 use bucket::Bucket;
-pub use cached::Cached;
+pub(crate) use bucket::Subscriber;
+pub use cached::{Cached, FetchStatus, QueryStatus};
 use entry::Entry;
 use state::State;
 
 #[derive(Debug, Default)]
 pub(crate) struct Cache {
     cache: TypeMap,
+    next_subscriber_id: usize,
 }
 
 impl Cache {
-    pub fn insert<Q>(&mut self, query: impl Into<Rc<Q>>, data: impl Into<Rc<Q::Output>>)
+    pub fn insert<Q>(&mut self, query: impl Into<Rc<Q>>, data: impl Into<Rc<Q::Ok>>)
     where
-        Q: Query + 'static,
+        Q: TryQuery + 'static,
     {
+        let query = query.into();
+        let bucket = self
+            .cache
+            .entry::<Bucket<Q>>()
+            .or_insert_with(Default::default);
+        bucket.touch(query.clone());
+        bucket.values.insert(query, State::valid(data));
+    }
+
+    /// Stores data which was fetched at the given time, rather than just now.
+    pub fn insert_at<Q>(
+        &mut self,
+        query: impl Into<Rc<Q>>,
+        data: impl Into<Rc<Q::Ok>>,
+        updated_at: f64,
+    ) where
+        Q: TryQuery + 'static,
+    {
+        let query = query.into();
         let bucket = self
             .cache
             .entry::<Bucket<Q>>()
             .or_insert_with(Default::default);
-        bucket.values.insert(query.into(), State::valid(data));
+        bucket.touch(query.clone());
+        bucket
+            .values
+            .insert(query, State::valid_at(data, updated_at));
+    }
+
+    /// Records a failed fetch, keeping any previously fetched data.
+    pub fn fail<Q>(&mut self, query: impl Into<Rc<Q>>, error: impl Into<Rc<Q::Error>>)
+    where
+        Q: TryQuery + 'static,
+    {
+        let query = query.into();
+        let bucket = self
+            .cache
+            .entry::<Bucket<Q>>()
+            .or_insert_with(Default::default);
+        bucket.touch(query.clone());
+        bucket.values.entry(query).or_default().set_error(error);
     }
 
     pub fn remove<Q>(&mut self, query: &Q)
     where
-        Q: Query + 'static,
+        Q: TryQuery + 'static,
     {
         if let Some(bucket) = self.cache.get_mut::<Bucket<Q>>() {
             bucket.values.remove(query);
+            if matches!(bucket.observers.get(query), Some(observers) if observers.subscribers.is_empty())
+            {
+                bucket.observers.remove(query);
+            }
+        }
+    }
+
+    /// Sets the default options for all queries of type `Q`.
+    pub fn set_defaults<Q>(&mut self, options: Options)
+    where
+        Q: TryQuery + 'static,
+    {
+        self.cache
+            .entry::<Bucket<Q>>()
+            .or_insert_with(Default::default)
+            .defaults = options;
+    }
+
+    /// The default options for all queries of type `Q`.
+    pub fn defaults<Q>(&self) -> Options
+    where
+        Q: TryQuery + 'static,
+    {
+        self.cache
+            .get::<Bucket<Q>>()
+            .map(|bucket| bucket.defaults.clone())
+            .unwrap_or_default()
+    }
+
+    /// Registers a subscriber to a query, which also prevents it from being garbage
+    /// collected. Returns the ID of the subscriber, to be used to unsubscribe.
+    pub fn subscribe<Q>(
+        &mut self,
+        query: impl Into<Rc<Q>>,
+        callback: Callback<Option<Cached<Q>>>,
+        options: SharedOptions,
+    ) -> usize
+    where
+        Q: TryQuery + 'static,
+    {
+        let id = self.next_subscriber_id;
+        self.next_subscriber_id += 1;
+        let bucket = self
+            .cache
+            .entry::<Bucket<Q>>()
+            .or_insert_with(Default::default);
+        bucket
+            .observers
+            .entry(query.into())
+            .or_default()
+            .subscribers
+            .push(Subscriber {
+                id,
+                callback,
+                options,
+            });
+        id
+    }
+
+    /// Unregisters a subscriber to a query. Returns `true` if the query is now unobserved,
+    /// in which case it should be scheduled for garbage collection.
+    pub fn unsubscribe<Q>(&mut self, query: &Q, id: usize) -> bool
+    where
+        Q: TryQuery + 'static,
+    {
+        let observers = self
+            .cache
+            .get_mut::<Bucket<Q>>()
+            .and_then(|bucket| bucket.observers.get_mut(query));
+        match observers {
+            Some(observers) if !observers.subscribers.is_empty() => {
+                observers
+                    .subscribers
+                    .retain(|subscriber| subscriber.id != id);
+                if observers.subscribers.is_empty() {
+                    observers.idle_since = clock::now();
+                    return true;
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// The current subscribers to a query.
+    pub fn subscribers<Q>(&self, query: &Q) -> &[Subscriber<Q>]
+    where
+        Q: TryQuery + 'static,
+    {
+        self.cache
+            .get::<Bucket<Q>>()
+            .and_then(|bucket| bucket.observers.get(query))
+            .map_or(&[], |observers| observers.subscribers.as_slice())
+    }
+
+    /// How the query is currently being refetched on an interval, if at all.
+    pub fn polling<Q>(&self, query: &Q) -> Option<&Polling>
+    where
+        Q: TryQuery + 'static,
+    {
+        self.cache
+            .get::<Bucket<Q>>()
+            .and_then(|bucket| bucket.observers.get(query))
+            .and_then(|observers| observers.polling.as_ref())
+    }
+
+    /// Replaces the interval refetching of an observed query, stopping any previous polling.
+    pub fn set_polling<Q>(&mut self, query: &Q, polling: Option<Polling>)
+    where
+        Q: TryQuery + 'static,
+    {
+        if let Some(observers) = self
+            .cache
+            .get_mut::<Bucket<Q>>()
+            .and_then(|bucket| bucket.observers.get_mut(query))
+        {
+            observers.polling = polling;
+        }
+    }
+
+    /// Removes a query from the cache if it has had no observers for at least `gc_time`.
+    pub fn collect<Q>(&mut self, query: &Q, gc_time: Duration)
+    where
+        Q: TryQuery + 'static,
+    {
+        let bucket = match self.cache.get_mut::<Bucket<Q>>() {
+            Some(bucket) => bucket,
+            None => return,
+        };
+        if let Some(observers) = bucket.observers.get(query) {
+            if !observers.subscribers.is_empty() || clock::elapsed(observers.idle_since) < gc_time {
+                return;
+            }
+        }
+        if matches!(bucket.values.get(query), Some(state) if state.is_loading()) {
+            return;
+        }
+        bucket.values.remove(query);
+        bucket.observers.remove(query);
+    }
+
+    /// Prevents fetches from overwriting a query's data, until it is released.
+    pub fn hold<Q>(&mut self, query: impl Into<Rc<Q>>)
+    where
+        Q: TryQuery + 'static,
+    {
+        let bucket = self
+            .cache
+            .entry::<Bucket<Q>>()
+            .or_insert_with(Default::default);
+        *bucket.holds.entry(query.into()).or_default() += 1;
+    }
+
+    pub fn release<Q>(&mut self, query: &Q)
+    where
+        Q: TryQuery + 'static,
+    {
+        if let Some(bucket) = self.cache.get_mut::<Bucket<Q>>() {
+            if let Some(holds) = bucket.holds.get_mut(query) {
+                *holds -= 1;
+                if *holds == 0 {
+                    bucket.holds.remove(query);
+                }
+            }
+        }
+    }
+
+    pub fn is_held<Q>(&self, query: &Q) -> bool
+    where
+        Q: TryQuery + 'static,
+    {
+        self.cache
+            .get::<Bucket<Q>>()
+            .is_some_and(|bucket| bucket.holds.contains_key(query))
+    }
+
+    /// Puts a query back to a previously retrieved state, or removes it if it was
+    /// not previously cached.
+    pub fn restore<Q>(&mut self, query: Rc<Q>, previous: Option<Cached<Q>>)
+    where
+        Q: TryQuery + 'static,
+    {
+        let bucket = self
+            .cache
+            .entry::<Bucket<Q>>()
+            .or_insert_with(Default::default);
+        match previous {
+            Some(Cached { mut data, .. }) => {
+                // A request which has completed since will never update this state.
+                if matches!(data.pending_data(), Some(request) if request.is_done()) {
+                    data.set_cancelled();
+                }
+                bucket.touch(query.clone());
+                bucket.values.insert(query, data);
+            }
+            None => {
+                bucket.values.remove(&query);
+            }
         }
     }
 
     pub fn invalidate<Q>(&mut self, query: &Q)
     where
-        Q: Query + 'static,
+        Q: TryQuery + 'static,
     {
         if let Some(bucket) = self.cache.get_mut::<Bucket<Q>>() {
             if let Some(state) = bucket.values.get_mut(query) {
@@ -53,7 +293,7 @@ impl Cache {
 
     pub fn get<Q, K>(&self, key: &K) -> Option<Cached<Q>>
     where
-        Q: Query + 'static,
+        Q: TryQuery + 'static,
         Rc<Q>: Borrow<K>,
         K: Hash + Eq,
     {
@@ -67,19 +307,22 @@ impl Cache {
 
     pub fn entry<Q>(&mut self, query: impl Into<Rc<Q>>) -> Entry<'_, Q>
     where
-        Q: Query + 'static,
+        Q: TryQuery + 'static,
     {
         let query = query.into();
         let bucket = self
             .cache
             .entry::<Bucket<Q>>()
             .or_insert_with(Default::default);
+        if !bucket.values.contains_key(&query) {
+            bucket.touch(query.clone());
+        }
         Entry::from(bucket.values.entry(query))
     }
 
-    pub fn get_mut<Q, K>(&mut self, query: &K) -> Option<&mut State<Q::Output>>
+    pub fn get_mut<Q, K>(&mut self, query: &K) -> Option<&mut State<Q::Ok, Q::Error>>
     where
-        Q: Query + 'static,
+        Q: TryQuery + 'static,
         Rc<Q>: Borrow<K>,
         K: Hash + Eq,
     {
@@ -87,3 +330,56 @@ impl Cache {
         bucket.values.get_mut(query)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::Query;
+    use crate::query_client::request::Request;
+    use futures::future::{pending, ready, Ready};
+
+    #[derive(Clone, Eq, PartialEq, Hash, Debug)]
+    struct Number(u32);
+
+    impl Query for Number {
+        type Output = u32;
+        type Future = Ready<u32>;
+
+        fn query(&self) -> Self::Future {
+            ready(self.0)
+        }
+    }
+
+    #[test]
+    fn collects_unobserved_queries_after_gc_time() {
+        let mut cache = Cache::default();
+        cache.insert::<Number>(Number(1), 1);
+        cache.collect(&Number(1), Duration::MAX);
+        assert!(cache.get::<Number, _>(&Number(1)).is_some());
+        cache.collect(&Number(1), Duration::ZERO);
+        assert!(cache.get::<Number, _>(&Number(1)).is_none());
+    }
+
+    #[test]
+    fn does_not_collect_observed_queries() {
+        let mut cache = Cache::default();
+        cache.insert::<Number>(Number(1), 1);
+        let id = cache.subscribe::<Number>(Number(1), Callback::noop(), Default::default());
+        cache.collect(&Number(1), Duration::ZERO);
+        assert!(cache.get::<Number, _>(&Number(1)).is_some());
+        assert!(cache.unsubscribe(&Number(1), id));
+        cache.collect(&Number(1), Duration::ZERO);
+        assert!(cache.get::<Number, _>(&Number(1)).is_none());
+    }
+
+    #[test]
+    fn does_not_collect_loading_queries() {
+        let mut cache = Cache::default();
+        cache
+            .entry::<Number>(Number(1))
+            .or_default()
+            .set_loading(Request::new(pending()));
+        cache.collect(&Number(1), Duration::ZERO);
+        assert!(cache.get::<Number, _>(&Number(1)).is_some());
+    }
+}
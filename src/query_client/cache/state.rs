@@ -1,34 +1,88 @@
+use super::{FetchStatus, QueryStatus};
+use crate::query_client::clock;
 use crate::query_client::request::Request;
 use std::rc::Rc;
+use std::time::Duration;
 
+/// Fetched data, along with the time at which it was fetched.
 #[derive(Debug)]
-pub(crate) enum State<T> {
-    Invalid(Rc<T>),
-    Valid(Rc<T>),
-    Loading(Option<Rc<T>>, Request<T>),
-    Idle,
+pub(crate) struct Data<T> {
+    pub value: Rc<T>,
+    pub updated_at: f64,
+}
+
+impl<T> Clone for Data<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+impl<T> Data<T> {
+    pub fn new(value: impl Into<Rc<T>>) -> Self {
+        Self {
+            value: value.into(),
+            updated_at: clock::now(),
+        }
+    }
+
+    pub fn is_stale(&self, stale_time: Duration) -> bool {
+        clock::elapsed(self.updated_at) >= stale_time
+    }
 }
 
-impl<T> Default for State<T> {
-    fn default() -> Self {
-        Self::Idle
+/// The most recent error, along with how many times in a row the query has failed.
+#[derive(Debug)]
+pub(crate) struct Failure<E> {
+    pub error: Rc<E>,
+    pub count: usize,
+}
+
+impl<E> Clone for Failure<E> {
+    fn clone(&self) -> Self {
+        Self {
+            error: self.error.clone(),
+            count: self.count,
+        }
     }
 }
 
-impl<T> Clone for State<T> {
+#[derive(Debug, Default)]
+pub(crate) enum State<T, E> {
+    Invalid(Data<T>),
+    Valid(Data<T>),
+    Loading(Option<Data<T>>, Option<Failure<E>>, Request<T, E>),
+    Error(Option<Data<T>>, Failure<E>),
+    #[default]
+    Idle,
+}
+
+impl<T, E> Clone for State<T, E> {
     fn clone(&self) -> Self {
         match self {
             Self::Invalid(data) => Self::Invalid(data.clone()),
             Self::Valid(data) => Self::Valid(data.clone()),
-            Self::Loading(data, req) => Self::Loading(data.clone(), req.clone()),
+            Self::Loading(data, failure, req) => {
+                Self::Loading(data.clone(), failure.clone(), req.clone())
+            }
+            Self::Error(data, failure) => Self::Error(data.clone(), failure.clone()),
             Self::Idle => Self::Idle,
         }
     }
 }
 
-impl<T> State<T> {
+impl<T, E> State<T, E> {
     pub fn valid(data: impl Into<Rc<T>>) -> Self {
-        Self::Valid(data.into())
+        Self::Valid(Data::new(data))
+    }
+
+    pub fn valid_at(data: impl Into<Rc<T>>, updated_at: f64) -> Self {
+        Self::Valid(Data {
+            value: data.into(),
+            updated_at,
+        })
     }
 
     pub fn set_invalid(&mut self) {
@@ -38,11 +92,57 @@ impl<T> State<T> {
         }
     }
 
-    pub fn set_loading(&mut self, req: Request<T>) {
+    pub fn set_loading(&mut self, req: Request<T, E>) {
+        *self = match std::mem::take(self) {
+            Self::Valid(data) | Self::Invalid(data) => Self::Loading(Some(data), None, req),
+            Self::Error(data, failure) => Self::Loading(data, Some(failure), req),
+            Self::Idle => Self::Loading(None, None, req),
+            Self::Loading(data, failure, _) => Self::Loading(data, failure, req),
+        }
+    }
+
+    /// Records a failure, keeping the last successfully fetched data.
+    pub fn set_error(&mut self, error: impl Into<Rc<E>>) {
+        let count = self.failure_count() + 1;
+        let failure = Failure {
+            error: error.into(),
+            count,
+        };
+        *self = match std::mem::take(self) {
+            Self::Valid(data) | Self::Invalid(data) => Self::Error(Some(data), failure),
+            Self::Loading(data, ..) | Self::Error(data, _) => Self::Error(data, failure),
+            Self::Idle => Self::Error(None, failure),
+        }
+    }
+
+    /// Records a failed attempt of a request that is going to be retried.
+    pub fn set_retrying(&mut self, error: impl Into<Rc<E>>) {
+        let count = self.failure_count() + 1;
+        if let Self::Loading(_, failure, _) = self {
+            *failure = Some(Failure {
+                error: error.into(),
+                count,
+            });
+        }
+    }
+
+    /// Abandons an in-flight request, going back to the previously fetched data,
+    /// which remains stale as it was being refetched.
+    pub fn set_cancelled(&mut self) {
+        *self = match std::mem::take(self) {
+            Self::Loading(data, Some(failure), _) => Self::Error(data, failure),
+            Self::Loading(Some(data), None, _) => Self::Invalid(data),
+            Self::Loading(None, None, _) => Self::Idle,
+            other => other,
+        }
+    }
+
+    /// Forgets a failure, going back to the previously fetched data, which is stale.
+    pub fn clear_error(&mut self) {
         *self = match std::mem::take(self) {
-            Self::Valid(data) | Self::Invalid(data) => Self::Loading(Some(data), req),
-            Self::Idle => Self::Loading(None, req),
-            Self::Loading(data, _) => Self::Loading(data, req),
+            Self::Error(Some(data), _) => Self::Invalid(data),
+            Self::Error(None, _) => Self::Idle,
+            other => other,
         }
     }
 
@@ -54,25 +154,198 @@ impl<T> State<T> {
         matches!(self, Self::Loading(..))
     }
 
-    pub fn pending_data(&self) -> Option<Request<T>> {
+    pub fn pending_data(&self) -> Option<Request<T, E>> {
         match self {
             Self::Loading(.., req) => Some(req.clone()),
             _ => None,
         }
     }
 
-    pub fn data(&self) -> Option<&T> {
+    fn last_data(&self) -> Option<&Data<T>> {
+        match self {
+            Self::Valid(data)
+            | Self::Invalid(data)
+            | Self::Loading(Some(data), ..)
+            | Self::Error(Some(data), _) => Some(data),
+            _ => None,
+        }
+    }
+
+    fn failure(&self) -> Option<&Failure<E>> {
         match self {
-            Self::Valid(data) | Self::Invalid(data) => Some(data.as_ref()),
+            Self::Error(_, failure) | Self::Loading(_, Some(failure), _) => Some(failure),
             _ => None,
         }
     }
 
+    /// The most recently fetched data, which continues to be available while
+    /// the query is being refetched.
+    pub fn data(&self) -> Option<&T> {
+        self.last_data().map(|data| data.value.as_ref())
+    }
+
+    pub fn data_rc(&self) -> Option<Rc<T>> {
+        self.last_data().map(|data| data.value.clone())
+    }
+
+    pub fn updated_at(&self) -> Option<f64> {
+        self.last_data().map(|data| data.updated_at)
+    }
+
+    /// The most recent error, which remains available while the query is being
+    /// refetched, until it succeeds.
+    pub fn error(&self) -> Option<&E> {
+        self.failure().map(|failure| failure.error.as_ref())
+    }
+
+    pub fn error_rc(&self) -> Option<Rc<E>> {
+        self.failure().map(|failure| failure.error.clone())
+    }
+
+    pub fn failure_count(&self) -> usize {
+        self.failure().map_or(0, |failure| failure.count)
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, Self::Error(..))
+    }
+
     pub fn is_valid(&self) -> bool {
         matches!(self, Self::Valid(..))
     }
 
+    /// Whether this data should be refetched: it is either not valid, or
+    /// was fetched longer than `stale_time` ago.
+    pub fn is_stale(&self, stale_time: Duration) -> bool {
+        match self {
+            Self::Valid(data) => data.is_stale(stale_time),
+            _ => true,
+        }
+    }
+
     pub fn is_idle(&self) -> bool {
         matches!(self, Self::Idle)
     }
+
+    pub fn status(&self) -> QueryStatus {
+        match self {
+            Self::Error(..) => QueryStatus::Error,
+            _ if self.last_data().is_some() => QueryStatus::Success,
+            _ => QueryStatus::Pending,
+        }
+    }
+
+    pub fn fetch_status(&self) -> FetchStatus {
+        match self {
+            Self::Loading(.., req) if req.is_paused() => FetchStatus::Paused,
+            Self::Loading(..) => FetchStatus::Fetching,
+            _ => FetchStatus::Idle,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::pending;
+
+    type TestState = State<u32, &'static str>;
+
+    fn request() -> Request<u32, &'static str> {
+        Request::new(pending())
+    }
+
+    #[test]
+    fn valid_data_becomes_stale_after_stale_time() {
+        let state = TestState::valid(1);
+        assert!(!state.is_stale(Duration::MAX));
+        assert!(state.is_stale(Duration::ZERO));
+        assert!(TestState::default().is_stale(Duration::MAX));
+    }
+
+    #[test]
+    fn invalid_data_is_stale() {
+        let mut state = TestState::valid(1);
+        state.set_invalid();
+        assert!(state.is_stale(Duration::MAX));
+        assert_eq!(state.data(), Some(&1));
+    }
+
+    #[test]
+    fn error_keeps_previous_data() {
+        let mut state = TestState::valid(1);
+        state.set_loading(request());
+        state.set_error("failed");
+        assert!(state.is_error());
+        assert_eq!(state.data(), Some(&1));
+        assert_eq!(state.error(), Some(&"failed"));
+        assert_eq!(state.failure_count(), 1);
+    }
+
+    #[test]
+    fn retries_count_as_failures() {
+        let mut state = TestState::default();
+        state.set_loading(request());
+        state.set_retrying("first");
+        assert!(state.is_loading());
+        state.set_error("second");
+        assert_eq!(state.failure_count(), 2);
+        assert_eq!(state.error(), Some(&"second"));
+    }
+
+    #[test]
+    fn cancelling_restores_previous_state() {
+        let mut state = TestState::valid(1);
+        state.set_loading(request());
+        state.set_cancelled();
+        assert!(matches!(state, State::Invalid(..)));
+        assert_eq!(state.data(), Some(&1));
+
+        let mut state = TestState::default();
+        state.set_loading(request());
+        state.set_cancelled();
+        assert!(state.is_idle());
+
+        let mut state = TestState::default();
+        state.set_error("failed");
+        state.set_loading(request());
+        state.set_cancelled();
+        assert!(state.is_error());
+        assert_eq!(state.failure_count(), 1);
+    }
+
+    #[test]
+    fn loading_keeps_previous_data() {
+        let mut state = TestState::valid(1);
+        state.set_loading(request());
+        assert!(state.is_loading());
+        assert_eq!(state.data(), Some(&1));
+        assert_eq!(state.status(), QueryStatus::Success);
+        assert_eq!(state.fetch_status(), FetchStatus::Fetching);
+    }
+
+    #[test]
+    fn paused_requests_are_reported() {
+        let mut state = TestState::default();
+        let request = request();
+        state.set_loading(request.clone());
+        request.set_paused(true);
+        assert_eq!(state.status(), QueryStatus::Pending);
+        assert_eq!(state.fetch_status(), FetchStatus::Paused);
+    }
+
+    #[test]
+    fn clearing_an_error_keeps_data() {
+        let mut state = TestState::valid(1);
+        state.set_error("failed");
+        state.clear_error();
+        assert!(matches!(state, State::Invalid(..)));
+        assert_eq!(state.data(), Some(&1));
+        assert_eq!(state.failure_count(), 0);
+
+        let mut state = TestState::default();
+        state.set_error("failed");
+        state.clear_error();
+        assert!(state.is_idle());
+    }
 }
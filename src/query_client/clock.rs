@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+/// The current time, in milliseconds since the Unix epoch.
+///
+/// `std::time::Instant` is not available in the browser, so timestamps
+/// are taken from the Javascript clock instead.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |since_epoch| since_epoch.as_secs_f64() * 1000.0)
+}
+
+/// The time that has passed since the given timestamp (as returned by [`now`][]).
+pub(crate) fn elapsed(since: f64) -> Duration {
+    Duration::from_secs_f64((now() - since).max(0.0) / 1000.0)
+}
+
+/// Waits for the given duration to pass.
+///
+/// Durations too long for the browser's timers to represent never complete.
+pub(crate) async fn sleep(duration: Duration) {
+    match u32::try_from(duration.as_millis()) {
+        Ok(millis) => timeout(millis).await,
+        Err(..) => futures::future::pending().await,
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn timeout(millis: u32) {
+    gloo_timers::future::TimeoutFuture::new(millis).await
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn timeout(millis: u32) {
+    futures_timer::Delay::new(Duration::from_millis(millis.into())).await
+}
+
+/// A random number between 0 (inclusive) and 1 (exclusive).
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn random() -> f64 {
+    js_sys::Math::random()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn random() -> f64 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    let bits = RandomState::new().build_hasher().finish() >> 11;
+    bits as f64 / (1u64 << 53) as f64
+}
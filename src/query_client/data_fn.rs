@@ -0,0 +1,27 @@
+use super::{runtime, QueryClient};
+use std::any::{type_name, Any};
+use std::rc::Rc;
+
+/// Provides data for a query, whose type is only checked once the data is used.
+pub(crate) type DataFn = dyn Fn(&QueryClient) -> Option<Rc<dyn Any>>;
+
+pub(crate) fn from_value<T: 'static>(value: T) -> Rc<DataFn> {
+    let value: Rc<dyn Any> = Rc::new(value);
+    Rc::new(move |_| Some(value.clone()))
+}
+
+pub(crate) fn from_fn<T: 'static>(f: impl Fn(&QueryClient) -> Option<T> + 'static) -> Rc<DataFn> {
+    Rc::new(move |client| f(client).map(|data| Rc::new(data) as Rc<dyn Any>))
+}
+
+/// Gets the data, if it is of type `T`, warning that `what` is ignored if it is not.
+pub(crate) fn get<T: 'static>(data_fn: &DataFn, client: &QueryClient, what: &str) -> Option<Rc<T>> {
+    let data = data_fn(client)?.downcast();
+    if data.is_err() {
+        runtime::warn(&format!(
+            "ignoring {what} which is not of the query's data type `{}`",
+            type_name::<T>()
+        ));
+    }
+    data.ok()
+}
@@ -0,0 +1,59 @@
+use super::Subscription;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use yew::Callback;
+
+/// A boolean which notifies its listeners whenever it changes.
+#[derive(Clone)]
+pub(crate) struct Flag(Rc<FlagInner>);
+
+struct FlagInner {
+    value: Cell<bool>,
+    listeners: RefCell<Vec<(usize, Callback<bool>)>>,
+    next_listener_id: Cell<usize>,
+}
+
+impl Flag {
+    pub fn new(value: bool) -> Self {
+        Self(Rc::new(FlagInner {
+            value: Cell::new(value),
+            listeners: RefCell::default(),
+            next_listener_id: Cell::new(0),
+        }))
+    }
+
+    pub fn get(&self) -> bool {
+        self.0.value.get()
+    }
+
+    pub fn set(&self, value: bool) {
+        if self.0.value.replace(value) == value {
+            return;
+        }
+        // Listeners may subscribe or unsubscribe while being notified.
+        let listeners: Vec<_> = self
+            .0
+            .listeners
+            .borrow()
+            .iter()
+            .map(|(_, callback)| callback.clone())
+            .collect();
+        for callback in listeners {
+            callback.emit(value);
+        }
+    }
+
+    pub fn subscribe(&self, callback: Callback<bool>) -> Subscription {
+        let id = self.0.next_listener_id.get();
+        self.0.next_listener_id.set(id + 1);
+        self.0.listeners.borrow_mut().push((id, callback));
+        let flag = Rc::downgrade(&self.0);
+        Subscription::new(move || {
+            if let Some(flag) = flag.upgrade() {
+                flag.listeners
+                    .borrow_mut()
+                    .retain(|(listener_id, _)| *listener_id != id);
+            }
+        })
+    }
+}
@@ -0,0 +1,109 @@
+use super::flag::Flag;
+use super::runtime;
+use super::Subscription;
+use gloo_events::EventListener;
+use std::cell::RefCell;
+use std::rc::Rc;
+use yew::Callback;
+
+/// Tracks whether the app is focused, so that queries can be refetched when it
+/// regains focus (see [`Options::refetch_on_window_focus`][super::Options::refetch_on_window_focus]).
+///
+/// By default, the [`QueryClient`][super::QueryClient] uses a [`WindowFocusManager`][].
+/// Another implementation may be provided with [`QueryClientBuilder::focus_manager`][super::QueryClientBuilder::focus_manager].
+pub trait FocusManager {
+    fn is_focused(&self) -> bool;
+
+    /// Calls `callback` whenever the app gains (`true`) or loses (`false`) focus,
+    /// until the returned [`Subscription`][] is dropped.
+    fn subscribe(&self, callback: Callback<bool>) -> Subscription;
+}
+
+/// Considers the app focused whenever the page is visible.
+#[derive(Clone)]
+pub struct WindowFocusManager {
+    focused: Flag,
+    listener: Rc<RefCell<Option<EventListener>>>,
+}
+
+impl WindowFocusManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for WindowFocusManager {
+    fn default() -> Self {
+        Self {
+            focused: Flag::new(true),
+            listener: Rc::default(),
+        }
+    }
+}
+
+impl FocusManager for WindowFocusManager {
+    fn is_focused(&self) -> bool {
+        !is_document_hidden()
+    }
+
+    fn subscribe(&self, callback: Callback<bool>) -> Subscription {
+        let mut listener = self.listener.borrow_mut();
+        if listener.is_none() {
+            if let Some(document) = runtime::window().and_then(|window| window.document()) {
+                self.focused.set(!document.hidden());
+                let focused = self.focused.clone();
+                *listener = Some(EventListener::new(
+                    &document,
+                    "visibilitychange",
+                    move |_| focused.set(!is_document_hidden()),
+                ));
+            }
+        }
+        self.focused.subscribe(callback)
+    }
+}
+
+fn is_document_hidden() -> bool {
+    runtime::window()
+        .and_then(|window| window.document())
+        .is_some_and(|document| document.hidden())
+}
+
+/// Tracks focus as set manually with [`set_focused`][ManualFocusManager::set_focused],
+/// such as in tests.
+///
+/// Clones share the same state, so a clone may be kept to drive the manager once
+/// it has been given to the [`QueryClient`][super::QueryClient].
+#[derive(Clone)]
+pub struct ManualFocusManager {
+    focused: Flag,
+}
+
+impl ManualFocusManager {
+    /// Create a manager which is initially focused.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_focused(&self, focused: bool) {
+        self.focused.set(focused);
+    }
+}
+
+impl Default for ManualFocusManager {
+    fn default() -> Self {
+        Self {
+            focused: Flag::new(true),
+        }
+    }
+}
+
+impl FocusManager for ManualFocusManager {
+    fn is_focused(&self) -> bool {
+        self.focused.get()
+    }
+
+    fn subscribe(&self, callback: Callback<bool>) -> Subscription {
+        self.focused.subscribe(callback)
+    }
+}
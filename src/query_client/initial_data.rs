@@ -0,0 +1,107 @@
+use super::data_fn::{self, DataFn};
+use super::QueryClient;
+use crate::query::TryQuery;
+use std::fmt::{self, Debug, Formatter};
+use std::rc::Rc;
+
+/// Data to seed the cache with for a query which has no data yet.
+///
+/// Unlike [`PlaceholderData`][super::PlaceholderData], initial data is stored in the
+/// cache as if it had been fetched, and is only refetched once it is stale.
+#[derive(Clone)]
+pub struct InitialData {
+    data: Rc<DataFn>,
+    updated_at: Option<f64>,
+}
+
+impl InitialData {
+    /// Seed the cache with the given data for `query`, checking that it is of the
+    /// query's [`Ok`][crate::query::TryQuery::Ok] type.
+    ///
+    /// ```
+    /// # use std::future::{ready, Ready};
+    /// use yew_query::prelude::*;
+    /// use yew_query::query_client::InitialData;
+    ///
+    /// #[derive(Clone, Eq, PartialEq, Hash)]
+    /// struct GetTitle;
+    ///
+    /// impl Query for GetTitle {
+    ///     type Output = String;
+    ///     type Future = Ready<String>;
+    ///
+    ///     fn query(&self) -> Self::Future {
+    ///         ready("Title".into())
+    ///     }
+    /// }
+    ///
+    /// let options = QueryOptions {
+    ///     initial_data: Some(InitialData::for_query(&GetTitle, "Loading...".into())),
+    ///     ..Default::default()
+    /// };
+    /// ```
+    pub fn for_query<Q: TryQuery>(_query: &Q, data: Q::Ok) -> Self
+    where
+        Q::Ok: 'static,
+    {
+        Self::value(data)
+    }
+
+    /// Seed the cache with the given data.
+    ///
+    /// The data is ignored if it is not of the query's [`Ok`][crate::query::TryQuery::Ok]
+    /// type, such as a `&str` given for a query of a `String`. Prefer
+    /// [`InitialData::for_query`][], which checks this.
+    pub fn value<T: 'static>(value: T) -> Self {
+        Self {
+            data: data_fn::from_value(value),
+            updated_at: None,
+        }
+    }
+
+    /// Seed the cache with the data returned by `f`, if any, such as from the cached
+    /// data of another query. As with [`InitialData::value`][], the data is ignored if
+    /// it is not of the query's [`Ok`][crate::query::TryQuery::Ok] type.
+    pub fn with<T: 'static>(f: impl Fn(&QueryClient) -> Option<T> + 'static) -> Self {
+        Self {
+            data: data_fn::from_fn(f),
+            updated_at: None,
+        }
+    }
+
+    /// Set when the initial data was fetched, in milliseconds since the Unix epoch, so
+    /// that it becomes stale according to the query's [`stale_time`][super::Options::stale_time].
+    ///
+    /// Default: the time at which the cache is seeded
+    pub fn updated_at(self, updated_at: f64) -> Self {
+        Self {
+            updated_at: Some(updated_at),
+            ..self
+        }
+    }
+
+    /// Gets the data, if it is of type `T`, along with when it was fetched.
+    pub(crate) fn get<T: 'static>(&self, client: &QueryClient) -> Option<(Rc<T>, Option<f64>)> {
+        Some((
+            data_fn::get(&*self.data, client, "initial data")?,
+            self.updated_at,
+        ))
+    }
+}
+
+impl PartialEq for InitialData {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.data, &other.data) && self.updated_at == other.updated_at
+    }
+}
+
+impl Eq for InitialData {}
+
+impl Debug for InitialData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InitialData")
+            .field("data", &"..")
+            .field("updated_at", &self.updated_at)
+            .finish()
+    }
+}
@@ -1,40 +1,143 @@
-use crate::query::Query;
-use futures::FutureExt;
+use crate::query::infinite::{Direction, Infinite, InfiniteQuery};
+use crate::query::TryQuery;
+use futures::channel::oneshot;
 use std::cell::RefCell;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
+use std::time::Duration;
 
+mod builder;
 mod cache;
+mod clock;
+pub(crate) mod data_fn;
+mod flag;
+mod focus_manager;
+mod initial_data;
+mod online_manager;
+mod optimistic_update;
+mod options;
+mod placeholder_data;
+mod polling;
 mod request;
+mod retry;
+pub(crate) mod runtime;
+mod subscription;
+mod throw_on_error;
 
 use cache::Cache;
+use options::ResolvedOptions;
+pub(crate) use options::{OptionsKey, SharedOptions};
+use polling::Polling;
 use request::Request;
+use runtime::spawn_local;
+use yew::Callback;
 
-pub use cache::Cached;
+pub use builder::QueryClientBuilder;
+pub use cache::{Cached, FetchStatus, QueryStatus};
+pub use focus_manager::{FocusManager, ManualFocusManager, WindowFocusManager};
+pub use initial_data::InitialData;
+pub use online_manager::{ManualOnlineManager, NavigatorOnlineManager, OnlineManager};
+pub use optimistic_update::OptimisticUpdate;
+pub use options::Options;
+pub use placeholder_data::PlaceholderData;
+pub use retry::{Retry, RetryDelay};
+pub use subscription::Subscription;
+pub use throw_on_error::ThrowOnError;
 
 /// Provides a backing for the query hooks. Must be provided to
 /// the app via [`QueryClientProvider`][crate::components::query_client_provider::QueryClientProvider].
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct QueryClient(Rc<RefCell<ClientInternals>>);
 
-#[derive(Default)]
 pub(crate) struct ClientInternals {
     cache: Cache,
+    default_options: Options,
+    focus_manager: Rc<dyn FocusManager>,
+    online_manager: Rc<dyn OnlineManager>,
+}
+
+impl Default for QueryClient {
+    fn default() -> Self {
+        QueryClientBuilder::new().build()
+    }
+}
+
+impl ClientInternals {
+    /// Merges options provided for a query over the defaults for its type, and
+    /// then over the client's defaults.
+    fn resolve_options<Q: TryQuery + 'static>(&self, options: &Options) -> ResolvedOptions {
+        let defaults = self.cache.defaults::<Q>().merge(&self.default_options);
+        options.merge(&defaults).resolve()
+    }
 }
 
 impl QueryClient {
     /// Create a new (default) `QueryClient`.
+    ///
+    /// To set default options for all queries, use [`QueryClient::builder`][] instead.
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Start building a `QueryClient` with default options for all queries.
+    pub fn builder() -> QueryClientBuilder {
+        QueryClientBuilder::new()
+    }
+
+    /// The defaults for queries of type `Q`, merged under `options`.
+    pub(crate) fn resolve_options<Q: TryQuery + 'static>(
+        &self,
+        options: &Options,
+    ) -> ResolvedOptions {
+        self.0.borrow().resolve_options::<Q>(options)
+    }
+
+    /// Set the default options for every query of type `Q`.
+    ///
+    /// These take precedence over the defaults set when building the client (see
+    /// [`QueryClientBuilder`][]), while options passed to individual hooks take
+    /// precedence over these. Replaces any defaults previously set for `Q`.
+    ///
+    /// For an [`InfiniteQuery`][] `I`, the defaults are set for [`Infinite<I>`][Infinite].
+    pub fn set_query_defaults<Q: TryQuery + 'static>(&self, options: Options) {
+        self.0.borrow_mut().cache.set_defaults::<Q>(options);
+    }
+
+    /// The default options for every query of type `Q`, as set by
+    /// [`set_query_defaults`][QueryClient::set_query_defaults].
+    pub fn get_query_defaults<Q: TryQuery + 'static>(&self) -> Options {
+        self.0.borrow().cache.defaults::<Q>()
+    }
+
     /// Manually set the output data for a particular query.
     ///
     /// This is particularly useful when you already have the data as a result of
     /// doing something else. If you do not yet have the data, you will be better
     /// served by [`fetch_query`][QueryClient::fetch_query]
-    pub fn set_query_data<Q: Query + 'static>(&self, query: Q, data: Q::Output) {
-        let mut client = self.0.borrow_mut();
-        client.cache.insert(query, data);
+    pub fn set_query_data<Q: TryQuery + 'static>(&self, query: Q, data: Q::Ok) {
+        self.set_query_data_rc(Rc::new(query), data);
+    }
+
+    fn set_query_data_rc<Q: TryQuery + 'static>(&self, query: Rc<Q>, data: Q::Ok) {
+        let gc_time = {
+            let mut client = self.0.borrow_mut();
+            client.cache.insert::<Q>(query.clone(), data);
+            client.resolve_options::<Q>(&Options::default()).gc_time
+        };
+        self.notify(query.as_ref());
+        self.schedule_gc(query, gc_time);
+    }
+
+    /// Begins an optimistic update, in which queries may be updated with the data
+    /// expected once a mutation completes, and restored if it fails.
+    ///
+    /// Typically, optimistic updates are made in [`Mutation::on_mutate`][crate::mutation::Mutation::on_mutate],
+    /// and are committed or rolled back automatically by the [`use_mutation`][crate::hooks::use_mutation::use_mutation]
+    /// hook depending on the result of the mutation.
+    ///
+    /// While the update is in progress, fetches of the touched queries will not
+    /// overwrite the optimistic data.
+    pub fn optimistic_update(&self) -> OptimisticUpdate {
+        OptimisticUpdate::new(self.clone())
     }
 
     /// Fetches a query and stores its data in the cache.
@@ -47,37 +150,256 @@ impl QueryClient {
     ///
     /// If this query was previously in the cache and is still valid, it will
     /// not be re-queried. Mark a query as invalid before attempting to fetch it
-    /// if you wish for it to be reloaded.
+    /// if you wish for it to be reloaded. Valid data which has become stale
+    /// (see [`stale_time`][Options::stale_time])
+    /// is refetched, while the stale data remains available from the cache.
+    ///
+    /// If the query fails, it is retried according to the [`retry`][Options::retry]
+    /// and [`retry_delay`][Options::retry_delay] options, all within the same request,
+    /// so every caller waiting on this query receives the result of the final attempt.
+    /// If it still fails, the error is recorded alongside any previously fetched
+    /// data (see [`Cached::error`][]), and the query will be attempted again the
+    /// next time it is fetched.
+    ///
+    /// While the app is offline (see [`OnlineManager`][]), the request is paused
+    /// before each attempt until it reconnects.
     ///
     /// If you wish to remove previously cached data before fetching, see
     /// [`clear_query`][QueryClient::clear_query].
-    pub async fn fetch_query<Q: Query + 'static>(&self, query: Q) -> Cached<Q> {
-        let query = Rc::new(query);
-        let client = self.0.clone();
-        let request = Request::new(query.query().map({
-            let query = query.clone();
-            let client = client.clone();
-            move |data| {
-                let data = Rc::new(data);
-                client.borrow_mut().cache.insert::<Q>(query, data.clone());
-                data
+    pub async fn fetch_query<Q: TryQuery + 'static>(&self, query: Q) -> Cached<Q> {
+        self.fetch_query_with_options(query, &Options::default())
+            .await
+    }
+
+    /// Fetches a query and stores its data in the cache, as with [`fetch_query`][QueryClient::fetch_query].
+    ///
+    /// The provided options override the client's defaults for this fetch.
+    pub async fn fetch_query_with_options<Q: TryQuery + 'static>(
+        &self,
+        query: Q,
+        options: &Options,
+    ) -> Cached<Q> {
+        self.fetch(Rc::new(query), options).await
+    }
+
+    /// Starts fetching a query in the background, as with [`fetch_query`][QueryClient::fetch_query],
+    /// without waiting for the result.
+    ///
+    /// This is useful to warm the cache with data which is likely to be needed soon,
+    /// such as when hovering over a link. Like [`fetch_query`][QueryClient::fetch_query],
+    /// the query is not refetched if its data is still fresh.
+    pub fn prefetch_query<Q: TryQuery + 'static>(&self, query: Q) {
+        self.prefetch_query_with_options(query, &Options::default())
+    }
+
+    /// Starts fetching a query in the background, as with [`prefetch_query`][QueryClient::prefetch_query].
+    ///
+    /// The provided options override the client's defaults for this fetch.
+    pub fn prefetch_query_with_options<Q: TryQuery + 'static>(&self, query: Q, options: &Options) {
+        let client = self.clone();
+        let options = options.clone();
+        spawn_local(async move {
+            client.fetch(Rc::new(query), &options).await;
+        });
+    }
+
+    /// Gets the data of a query, fetching it only if it is missing or stale.
+    ///
+    /// If the cached data is still fresh, the returned future completes immediately.
+    /// Otherwise, the query is fetched as with [`fetch_query`][QueryClient::fetch_query].
+    /// Returns `None` if the query failed (or was cancelled) before it had any data.
+    pub async fn ensure_query_data<Q: TryQuery + 'static>(&self, query: Q) -> Option<Rc<Q::Ok>> {
+        self.ensure_query_data_with_options(query, &Options::default())
+            .await
+    }
+
+    /// Gets the data of a query, as with [`ensure_query_data`][QueryClient::ensure_query_data].
+    ///
+    /// The provided options override the client's defaults for this fetch.
+    pub async fn ensure_query_data_with_options<Q: TryQuery + 'static>(
+        &self,
+        query: Q,
+        options: &Options,
+    ) -> Option<Rc<Q::Ok>> {
+        self.fetch(Rc::new(query), options).await.data_rc()
+    }
+
+    async fn fetch<Q: TryQuery + 'static>(&self, query: Rc<Q>, options: &Options) -> Cached<Q> {
+        self.seed_initial_data(query.clone(), options);
+        let (pending, previous) = {
+            let mut client = self.0.borrow_mut();
+            let stale_time = client.resolve_options::<Q>(options).stale_time;
+            let state = client.cache.entry::<Q>(query.clone()).or_default();
+            if !state.is_loading() && !state.is_stale(stale_time) {
+                return client.cache.get(query.as_ref()).unwrap();
             }
-        }));
+            (state.pending_data(), state.data_rc())
+        };
+        let request = match pending {
+            Some(request) => request,
+            None => self.start_request(query.clone(), options, None, {
+                let query = query.clone();
+                move || match &previous {
+                    Some(previous) => query.try_refetch(previous.clone()),
+                    None => query.try_query(),
+                }
+            }),
+        };
+        self.wait(query, request).await
+    }
 
-        let mut client_mut = client.borrow_mut();
-        let state = client_mut.cache.entry::<Q>(query.clone()).or_default();
-        if state.is_loading() {
-            let data = state.pending_data().unwrap();
-            std::mem::drop(client_mut);
-            data.await;
-        } else if !state.is_valid() {
-            state.set_loading(request.clone());
-            std::mem::drop(client_mut);
-            request.await;
+    /// Stores the [`initial_data`][Options::initial_data] for a query, if it has no data yet.
+    pub(crate) fn seed_initial_data<Q: TryQuery + 'static>(&self, query: Rc<Q>, options: &Options) {
+        let (data, updated_at) = match self.initial_data(query.as_ref(), options) {
+            Some(initial_data) => initial_data,
+            None => return,
+        };
+        let gc_time = self.resolve_options::<Q>(options).gc_time;
+        self.0
+            .borrow_mut()
+            .cache
+            .insert_at::<Q>(query.clone(), data, updated_at);
+        self.notify(query.as_ref());
+        self.schedule_gc(query, gc_time);
+    }
+
+    /// The [`initial_data`][Options::initial_data] for a query and when it was last
+    /// updated, if the query has no data yet. Nothing is stored in the cache.
+    pub(crate) fn initial_data<Q: TryQuery + 'static>(
+        &self,
+        query: &Q,
+        options: &Options,
+    ) -> Option<(Rc<Q::Ok>, f64)> {
+        let initial_data = {
+            let client = self.0.borrow();
+            let has_data = client
+                .cache
+                .get(query)
+                .is_some_and(|cached| cached.data().is_some() || cached.is_loading());
+            if has_data {
+                return None;
+            }
+            client.resolve_options::<Q>(options).initial_data?
+        };
+        // The initial data may be computed from the client, so it must not be borrowed.
+        let (data, updated_at) = initial_data.get::<Q::Ok>(self)?;
+        Some((data, updated_at.unwrap_or_else(clock::now)))
+    }
+
+    /// Fetches a query even if its data is fresh, as with [`fetch_query_with_options`][QueryClient::fetch_query_with_options]
+    /// after invalidating it. If the query is already being fetched, waits on that fetch instead.
+    pub(crate) async fn refetch<Q: TryQuery + 'static>(
+        &self,
+        query: Rc<Q>,
+        options: &Options,
+    ) -> Cached<Q> {
+        self.0.borrow_mut().cache.invalidate(query.as_ref());
+        self.fetch(query, options).await
+    }
+
+    /// Fetches the page after those already loaded for an [`InfiniteQuery`][], adding it
+    /// to the cached [`InfiniteData`][crate::query::infinite::InfiniteData].
+    ///
+    /// If no pages have been loaded yet, the first page is fetched instead. If there is
+    /// no next page, nothing is fetched. If the query is already being fetched, this waits
+    /// for that fetch to complete before fetching the next page, unless that fetch is
+    /// already fetching the next page.
+    pub async fn fetch_next_page<I: InfiniteQuery + Clone + 'static>(
+        &self,
+        query: I,
+        options: &Options,
+    ) -> Cached<Infinite<I>> {
+        self.fetch_page(Rc::new(Infinite(query)), options, Direction::Next)
+            .await
+    }
+
+    /// Fetches the page before those already loaded for an [`InfiniteQuery`][], as with
+    /// [`fetch_next_page`][QueryClient::fetch_next_page].
+    pub async fn fetch_previous_page<I: InfiniteQuery + Clone + 'static>(
+        &self,
+        query: I,
+        options: &Options,
+    ) -> Cached<Infinite<I>> {
+        self.fetch_page(Rc::new(Infinite(query)), options, Direction::Previous)
+            .await
+    }
+
+    async fn fetch_page<I: InfiniteQuery + Clone + 'static>(
+        &self,
+        query: Rc<Infinite<I>>,
+        options: &Options,
+        direction: Direction,
+    ) -> Cached<Infinite<I>> {
+        // The new page must follow on from the latest data. If the same page is
+        // already being fetched, that fetch is all that is needed.
+        while let Some(request) = self
+            .get_query_data(query.as_ref())
+            .and_then(|cached| cached.pending_request())
+        {
+            if request.page() == Some(direction) {
+                return self.wait(query, request).await;
+            }
+            self.wait(query.clone(), request).await;
         }
+        let cached = match self.get_query_data(query.as_ref()) {
+            Some(cached) => cached,
+            None => return self.fetch(query, options).await,
+        };
+        let previous = match cached.data_rc() {
+            Some(previous) => previous,
+            None => return self.fetch(query, options).await,
+        };
+        let page_param = match query.page_param(&previous, direction) {
+            Some(page_param) => page_param,
+            None => return cached,
+        };
+        let request = self.start_request(query.clone(), options, Some(direction), {
+            let query = query.clone();
+            move || query.fetch_page(previous.clone(), page_param.clone(), direction)
+        });
+        self.wait(query, request).await
+    }
+
+    /// Starts a request for a query, which performs `attempt` (retrying as necessary)
+    /// and stores its result in the cache. `page` is the page being fetched, if the
+    /// request is fetching another page of an infinite query.
+    fn start_request<Q: TryQuery + 'static>(
+        &self,
+        query: Rc<Q>,
+        options: &Options,
+        page: Option<Direction>,
+        attempt: impl Fn() -> Q::Future + 'static,
+    ) -> Request<Q::Ok, Q::Error> {
+        let request = {
+            let mut client = self.0.borrow_mut();
+            let options = client.resolve_options::<Q>(options);
+            let request = Request::new(self.clone().run_query(query.clone(), attempt, options))
+                .for_page(page);
+            client
+                .cache
+                .entry::<Q>(query.clone())
+                .or_default()
+                .set_loading(request.clone());
+            request
+        };
+        self.notify(query.as_ref());
+        request
+    }
 
-        let client = client.borrow();
-        client.cache.get(query.as_ref()).unwrap()
+    /// Waits for a request for a query to complete, returning the resulting state of the query.
+    async fn wait<Q: TryQuery + 'static>(
+        &self,
+        query: Rc<Q>,
+        request: Request<Q::Ok, Q::Error>,
+    ) -> Cached<Q> {
+        {
+            let _waiter = Waiter::new(self.clone(), query.clone(), request.clone());
+            // Errors and cancellations are recorded in the cache, and are read from there below.
+            let _ = request.await;
+        }
+        // The query may have been removed while it was being fetched.
+        let cached = self.0.borrow().cache.get(query.as_ref());
+        cached.unwrap_or_else(|| Cached::empty(query))
     }
 
     /// Retrieves cached query data.
@@ -88,39 +410,383 @@ impl QueryClient {
     ///
     /// The returned data is a snapshot of the state of the query, and will not update
     /// automatically when the query is completed. For that, see [`fetch_query`][QueryClient::fetch_query]
-    pub fn get_query_data<Q: Query + 'static>(&self, query: &Q) -> Option<Cached<Q>> {
+    /// or [`subscribe_query`][QueryClient::subscribe_query].
+    pub fn get_query_data<Q: TryQuery + 'static>(&self, query: &Q) -> Option<Cached<Q>> {
         let client = self.0.borrow();
         client.cache.get(query)
     }
 
-    /// Invalidate cached query data, without refetching.
+    /// Invalidate cached query data.
     ///
-    /// The invalid data will continue to be accessible, and may be refetched
-    /// manually at a later time.
+    /// If the query is currently being observed (e.g. by a mounted [`use_query`][crate::hooks::use_query::use_query]
+    /// hook which is enabled), it is refetched in the background. Otherwise, it is only
+    /// marked invalid, and will be refetched the next time it is used.
+    ///
+    /// The invalid data will continue to be accessible until it is refetched.
     ///
-    /// If you also want to refetch the data, use [`fetch_query`][QueryClient::fetch_query].
     /// If you want to completely remove the data and query, see [`clear_query`][QueryClient::clear_query].
-    pub fn invalidate_query<Q: Query + 'static>(&self, query: &Q) {
-        let mut client = self.0.borrow_mut();
-        client.cache.invalidate(query)
+    pub fn invalidate_query<Q: TryQuery + 'static>(&self, query: &Q) {
+        let refetch = {
+            let mut client = self.0.borrow_mut();
+            client.cache.invalidate(query);
+            let options = client
+                .cache
+                .subscribers(query)
+                .iter()
+                .map(|subscriber| subscriber.options.borrow().clone())
+                .find(|options| client.resolve_options::<Q>(options).enabled);
+            options.zip(client.cache.get(query))
+        };
+        self.notify(query);
+        if let Some((options, cached)) = refetch {
+            let client = self.clone();
+            spawn_local(async move {
+                client.fetch(cached.query, &options).await;
+            });
+        }
     }
 
     /// Completely remove a query and its associated data from the cache.
     ///
     /// After this, calling [`get_query_data`][QueryClient::get_query_data] will
     /// return `None` (as if this query had never been made).
-    pub fn remove_query<Q: Query + 'static>(&self, query: &Q) {
-        let mut client = self.0.borrow_mut();
-        client.cache.remove(query)
+    pub fn remove_query<Q: TryQuery + 'static>(&self, query: &Q) {
+        self.0.borrow_mut().cache.remove(query);
+        self.notify(query);
+    }
+
+    /// Cancels an in-flight fetch of a query.
+    ///
+    /// The query's future will not be polled again, and the cached data goes back to
+    /// what it was before the fetch began. Any callers of [`fetch_query`][QueryClient::fetch_query]
+    /// waiting on this fetch will receive that previous data.
+    ///
+    /// Fetches are also cancelled automatically when every caller waiting on them
+    /// has been dropped, such as when the last [`use_query`][crate::hooks::use_query::use_query]
+    /// hook fetching the query is unmounted.
+    pub fn cancel_query<Q: TryQuery + 'static>(&self, query: &Q) {
+        let cancelled = {
+            let mut client = self.0.borrow_mut();
+            match client.cache.get_mut(query) {
+                Some(state) => match state.pending_data() {
+                    Some(request) => {
+                        request.abort();
+                        state.set_cancelled();
+                        true
+                    }
+                    None => false,
+                },
+                None => false,
+            }
+        };
+        if cancelled {
+            self.notify(query);
+        }
     }
 
     /// Removed cached query data without triggering a refresh, but leaving the
     /// empty entry in the cache.
-    pub fn clear_query<Q: Query + 'static>(&self, query: &Q) {
-        let mut client = self.0.borrow_mut();
-        if let Some(state) = client.cache.get_mut(query) {
+    pub fn clear_query<Q: TryQuery + 'static>(&self, query: &Q) {
+        if let Some(state) = self.0.borrow_mut().cache.get_mut(query) {
             state.clear();
         }
+        self.notify(query);
+    }
+
+    /// Forgets the error of a failed query without triggering a refresh, keeping any
+    /// previously fetched data, which is then considered stale.
+    ///
+    /// This is used by [`QueryErrorBoundary`][crate::components::query_error_boundary::QueryErrorBoundary]
+    /// so that the query is not reported to the boundary again as soon as it is reset.
+    pub fn reset_query_error<Q: TryQuery + 'static>(&self, query: &Q) {
+        if let Some(state) = self.0.borrow_mut().cache.get_mut(query) {
+            state.clear_error();
+        }
+        self.notify(query);
+    }
+
+    /// Subscribes to changes of a query in the cache.
+    ///
+    /// The callback is called with a snapshot of the query's state whenever it
+    /// changes: when it begins fetching, when it is fetched (or fails), and when
+    /// it is manually set, invalidated, cleared or removed. A removed query is
+    /// reported as `None`.
+    ///
+    /// A query with subscribers is considered observed, and will not be garbage
+    /// collected. The provided options (in particular [`gc_time`][Options::gc_time],
+    /// [`refetch_interval`][Options::refetch_interval], [`refetch_on_window_focus`][Options::refetch_on_window_focus]
+    /// and [`refetch_on_reconnect`][Options::refetch_on_reconnect]) describe how the
+    /// subscriber is using the query.
+    ///
+    /// The subscription lasts until the returned [`Subscription`][] is dropped.
+    pub fn subscribe_query<Q: TryQuery + 'static>(
+        &self,
+        query: Q,
+        options: &Options,
+        callback: impl Into<Callback<Option<Cached<Q>>>>,
+    ) -> Subscription {
+        self.subscribe_query_with_shared_options(
+            query,
+            Rc::new(RefCell::new(options.clone())),
+            callback,
+        )
+    }
+
+    /// Subscribes to changes of a query in the cache, as with [`subscribe_query`][QueryClient::subscribe_query],
+    /// with options which may be replaced while subscribed.
+    pub(crate) fn subscribe_query_with_shared_options<Q: TryQuery + 'static>(
+        &self,
+        query: Q,
+        options: SharedOptions,
+        callback: impl Into<Callback<Option<Cached<Q>>>>,
+    ) -> Subscription {
+        let query = Rc::new(query);
+        let id =
+            self.0
+                .borrow_mut()
+                .cache
+                .subscribe(query.clone(), callback.into(), options.clone());
+        self.update_polling(query.clone());
+        let (focus_manager, online_manager) = {
+            let client = self.0.borrow();
+            (client.focus_manager.clone(), client.online_manager.clone())
+        };
+        let on_focus = focus_manager.subscribe(self.refetch_when(
+            query.clone(),
+            options.clone(),
+            |options| options.refetch_on_window_focus,
+        ));
+        let on_reconnect = online_manager.subscribe(self.refetch_when(
+            query.clone(),
+            options.clone(),
+            |options| options.refetch_on_reconnect,
+        ));
+        let client = self.clone();
+        Subscription::new(move || {
+            drop(on_focus);
+            drop(on_reconnect);
+            client.unsubscribe_query(query, id, &options.borrow())
+        })
+    }
+}
+
+impl QueryClient {
+    /// Unregisters a subscriber of a query, scheduling it to be garbage collected
+    /// after `gc_time` if it was the last one.
+    fn unsubscribe_query<Q: TryQuery + 'static>(&self, query: Rc<Q>, id: usize, options: &Options) {
+        let (unobserved, gc_time) = {
+            let mut client = self.0.borrow_mut();
+            let unobserved = client.cache.unsubscribe(query.as_ref(), id);
+            (unobserved, client.resolve_options::<Q>(options).gc_time)
+        };
+        self.update_polling(query.clone());
+        if unobserved {
+            self.schedule_gc(query, gc_time);
+        }
+    }
+
+    /// Creates a callback which refetches an observed query (if it is stale) whenever
+    /// it is called with `true`, as long as `refetch_on` allows it.
+    fn refetch_when<Q: TryQuery + 'static>(
+        &self,
+        query: Rc<Q>,
+        options: SharedOptions,
+        refetch_on: fn(&ResolvedOptions) -> bool,
+    ) -> Callback<bool> {
+        let client = Rc::downgrade(&self.0);
+        Callback::from(move |signal: bool| {
+            let client = match Weak::upgrade(&client) {
+                Some(client) if signal => QueryClient(client),
+                _ => return,
+            };
+            let options = options.borrow().clone();
+            let resolved = client.resolve_options::<Q>(&options);
+            if resolved.enabled && refetch_on(&resolved) {
+                let query = query.clone();
+                spawn_local(async move {
+                    client.fetch(query, &options).await;
+                });
+            }
+        })
+    }
+
+    /// Starts, stops or adjusts the interval refetching of a query to suit its
+    /// current subscribers.
+    fn update_polling<Q: TryQuery + 'static>(&self, query: Rc<Q>) {
+        let mut client = self.0.borrow_mut();
+        let mut interval = Duration::MAX;
+        let mut in_background = false;
+        for subscriber in client.cache.subscribers(query.as_ref()) {
+            let options = client.resolve_options::<Q>(&subscriber.options.borrow());
+            if options.enabled && options.refetch_interval < interval {
+                interval = options.refetch_interval;
+            }
+            if options.enabled && options.refetch_interval != Duration::MAX {
+                in_background |= options.refetch_interval_in_background;
+            }
+        }
+        let polling = client.cache.polling(query.as_ref());
+        if interval == Duration::MAX {
+            if polling.is_some() {
+                client.cache.set_polling(query.as_ref(), None);
+            }
+        } else if !polling.is_some_and(|polling| polling.is_polling(interval, in_background)) {
+            let polling = Polling::start(self, query.clone(), interval, in_background);
+            client.cache.set_polling(query.as_ref(), Some(polling));
+        }
+    }
+
+    /// Notifies the subscribers of a query of its current state.
+    ///
+    /// Must not be called while the client is borrowed, as subscribers may
+    /// interact with the client.
+    fn notify<Q: TryQuery + 'static>(&self, query: &Q) {
+        let (cached, callbacks) = {
+            let client = self.0.borrow();
+            let callbacks: Vec<_> = client
+                .cache
+                .subscribers(query)
+                .iter()
+                .map(|subscriber| subscriber.callback.clone())
+                .collect();
+            (client.cache.get(query), callbacks)
+        };
+        for callback in callbacks {
+            callback.emit(cached.clone());
+        }
+    }
+
+    /// Performs a query, retrying as necessary, and stores its result in the cache.
+    async fn run_query<Q: TryQuery + 'static>(
+        self,
+        query: Rc<Q>,
+        attempt: impl Fn() -> Q::Future,
+        options: ResolvedOptions,
+    ) -> Result<Rc<Q::Ok>, Rc<Q::Error>> {
+        let mut failure_count = 0;
+        let mut result = loop {
+            self.until_online(query.as_ref()).await;
+            match attempt().await {
+                Ok(data) => break Ok(Rc::new(data)),
+                Err(error) => {
+                    failure_count += 1;
+                    let error = Rc::new(error);
+                    if !options.retry.should_retry(failure_count, error.as_ref()) {
+                        break Err(error);
+                    }
+                    if let Some(state) = self.0.borrow_mut().cache.get_mut(query.as_ref()) {
+                        state.set_retrying(error);
+                    }
+                    self.notify(query.as_ref());
+                    clock::sleep(options.retry_delay.delay(failure_count)).await;
+                }
+            }
+        };
+        {
+            let mut client = self.0.borrow_mut();
+            if client.cache.is_held(query.as_ref()) {
+                // An optimistic update is in progress, and must not be overwritten.
+                if let Some(state) = client.cache.get_mut(query.as_ref()) {
+                    state.set_cancelled();
+                }
+            } else {
+                match &mut result {
+                    Ok(data) => {
+                        let previous = client
+                            .cache
+                            .get(query.as_ref())
+                            .and_then(|cached| cached.data_rc());
+                        if let Some(previous) = previous {
+                            *data = query.share_structure(&previous, data.clone());
+                        }
+                        client.cache.insert::<Q>(query.clone(), data.clone())
+                    }
+                    Err(error) => client.cache.fail::<Q>(query.clone(), error.clone()),
+                }
+            }
+        }
+        self.notify(query.as_ref());
+        self.schedule_gc(query, options.gc_time);
+        result
+    }
+
+    /// Waits for the network to come back if it is offline, pausing the request for
+    /// the query in the meantime.
+    async fn until_online<Q: TryQuery + 'static>(&self, query: &Q) {
+        let online_manager = self.0.borrow().online_manager.clone();
+        if online_manager.is_online() {
+            return;
+        }
+        let (sender, receiver) = oneshot::channel();
+        let sender = RefCell::new(Some(sender));
+        let _subscription = online_manager.subscribe(Callback::from(move |online| {
+            if let Some(sender) = sender.borrow_mut().take().filter(|_| online) {
+                let _ = sender.send(());
+            }
+        }));
+        self.set_paused(query, true);
+        let _ = receiver.await;
+        self.set_paused(query, false);
+    }
+
+    fn set_paused<Q: TryQuery + 'static>(&self, query: &Q, paused: bool) {
+        if let Some(request) = self
+            .0
+            .borrow()
+            .cache
+            .get(query)
+            .and_then(|cached| cached.pending_request())
+        {
+            request.set_paused(paused);
+        }
+        self.notify(query);
+    }
+
+    fn schedule_gc<Q: TryQuery + 'static>(&self, query: Rc<Q>, gc_time: Duration) {
+        if gc_time == Duration::MAX {
+            return;
+        }
+        let client = Rc::downgrade(&self.0);
+        spawn_local(async move {
+            clock::sleep(gc_time).await;
+            if let Some(client) = Weak::upgrade(&client) {
+                client.borrow_mut().cache.collect(query.as_ref(), gc_time);
+            }
+        });
+    }
+}
+
+/// Registers a caller waiting on a request for as long as it is alive, cancelling
+/// the request if it was the last.
+struct Waiter<Q: TryQuery + 'static> {
+    client: QueryClient,
+    query: Rc<Q>,
+    request: Request<Q::Ok, Q::Error>,
+}
+
+impl<Q: TryQuery + 'static> Waiter<Q> {
+    fn new(client: QueryClient, query: Rc<Q>, request: Request<Q::Ok, Q::Error>) -> Self {
+        request.add_waiter();
+        Self {
+            client,
+            query,
+            request,
+        }
+    }
+}
+
+impl<Q: TryQuery + 'static> Drop for Waiter<Q> {
+    fn drop(&mut self) {
+        if !self.request.remove_waiter() {
+            return;
+        }
+        let is_pending = matches!(
+            self.client.get_query_data(self.query.as_ref()),
+            Some(cached) if cached.is_pending(&self.request)
+        );
+        if is_pending {
+            self.client.cancel_query(self.query.as_ref());
+        }
     }
 }
 
@@ -129,3 +795,777 @@ impl PartialEq for QueryClient {
         Rc::ptr_eq(&self.0, &other.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::{share_if_equal, Query};
+    use futures::channel::oneshot;
+    use futures::future::{pending, ready, Pending, Ready};
+    use futures::FutureExt;
+    use std::cell::Cell;
+    use std::future::Future;
+    use std::pin::Pin;
+
+    #[derive(Clone, Eq, PartialEq, Hash, Debug)]
+    struct Number(u32);
+
+    impl Query for Number {
+        type Output = u32;
+        type Future = Ready<u32>;
+
+        fn query(&self) -> Self::Future {
+            ready(self.0)
+        }
+    }
+
+    /// Counts how many times it has been fetched.
+    #[derive(Clone, Default, Debug)]
+    struct Counter(Rc<Cell<u32>>);
+
+    impl PartialEq for Counter {
+        fn eq(&self, _: &Self) -> bool {
+            true
+        }
+    }
+
+    impl Eq for Counter {}
+
+    impl std::hash::Hash for Counter {
+        fn hash<H: std::hash::Hasher>(&self, _: &mut H) {}
+    }
+
+    impl Query for Counter {
+        type Output = u32;
+        type Future = Ready<u32>;
+
+        fn query(&self) -> Self::Future {
+            self.0.set(self.0.get() + 1);
+            ready(self.0.get())
+        }
+    }
+
+    #[test]
+    fn refetches_stale_data() {
+        let client = QueryClient::new();
+        let counter = Counter::default();
+        let cached = runtime::block_on(client.fetch_query(counter.clone()));
+        assert_eq!(cached.data(), Some(&1));
+        // Data is fresh forever by default.
+        let cached = runtime::block_on(client.fetch_query(counter.clone()));
+        assert_eq!(cached.data(), Some(&1));
+        let options = Options {
+            stale_time: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        let cached = runtime::block_on(client.fetch_query_with_options(counter.clone(), &options));
+        assert_eq!(cached.data(), Some(&2));
+
+        let client = QueryClient::builder().stale_time(Duration::ZERO).build();
+        let cached = runtime::block_on(client.fetch_query(counter.clone()));
+        assert_eq!(cached.data(), Some(&3));
+        let cached = runtime::block_on(client.fetch_query(counter));
+        assert_eq!(cached.data(), Some(&4));
+    }
+
+    #[test]
+    fn collects_queries_once_unobserved() {
+        let client = QueryClient::new();
+        client.set_query_data(Counter::default(), 1);
+        let options = Options {
+            gc_time: Some(Duration::from_millis(5)),
+            ..Default::default()
+        };
+        drop(client.subscribe_query(Counter::default(), &options, Callback::noop()));
+        assert!(client.get_query_data(&Counter::default()).is_some());
+        runtime::block_on(clock::sleep(Duration::from_millis(10)));
+        assert!(client.get_query_data(&Counter::default()).is_none());
+    }
+
+    /// Fails on every other attempt, starting with the first.
+    #[derive(Clone, Default, Eq, PartialEq, Hash, Debug)]
+    struct Flaky(Counter);
+
+    impl TryQuery for Flaky {
+        type Ok = u32;
+        type Error = u32;
+        type Future = Ready<Result<u32, u32>>;
+
+        fn try_query(&self) -> Self::Future {
+            let attempt = self.0.query().into_inner();
+            ready(if attempt % 2 == 1 {
+                Err(attempt)
+            } else {
+                Ok(attempt)
+            })
+        }
+    }
+
+    #[test]
+    fn keeps_data_when_a_refetch_fails() {
+        let client = QueryClient::builder().retry(Retry::Count(0)).build();
+        let query = Flaky::default();
+        let cached = runtime::block_on(client.fetch_query(query.clone()));
+        assert!(cached.is_error());
+        assert_eq!(cached.error(), Some(&1));
+        assert_eq!(cached.data(), None);
+        let cached = runtime::block_on(client.fetch_query(query.clone()));
+        assert_eq!(cached.data(), Some(&2));
+        assert!(!cached.is_error());
+        client.invalidate_query(&query);
+        let cached = runtime::block_on(client.fetch_query(query));
+        assert_eq!(cached.error(), Some(&3));
+        assert_eq!(cached.data(), Some(&2));
+    }
+
+    #[test]
+    fn retries_failed_queries() {
+        let client = QueryClient::builder()
+            .retry_delay(RetryDelay::Fixed(Duration::ZERO))
+            .build();
+        let query = Flaky::default();
+        let cached = runtime::block_on(client.fetch_query(query.clone()));
+        assert_eq!(cached.data(), Some(&2));
+        assert_eq!(cached.failure_count(), 0);
+
+        client.invalidate_query(&query);
+        let options = Options {
+            retry: Some(Retry::when(|_, error: &u32| *error < 4)),
+            ..Default::default()
+        };
+        let cached = runtime::block_on(client.fetch_query_with_options(query.clone(), &options));
+        assert_eq!(cached.data(), Some(&4));
+
+        client.invalidate_query(&query);
+        let options = Options {
+            retry: Some(Retry::Count(0)),
+            ..Default::default()
+        };
+        let cached = runtime::block_on(client.fetch_query_with_options(query.clone(), &options));
+        assert_eq!(cached.error(), Some(&5));
+        assert_eq!(cached.failure_count(), 1);
+        assert_eq!(cached.data(), Some(&4));
+    }
+
+    /// Never completes.
+    #[derive(Clone, Eq, PartialEq, Hash, Debug)]
+    struct Forever;
+
+    impl Query for Forever {
+        type Output = u32;
+        type Future = Pending<u32>;
+
+        fn query(&self) -> Self::Future {
+            pending()
+        }
+    }
+
+    #[test]
+    fn cancels_queries() {
+        let client = QueryClient::new();
+        client.set_query_data(Forever, 1);
+        client.invalidate_query(&Forever);
+        runtime::spawn_local({
+            let client = client.clone();
+            async move {
+                let cached = client.fetch_query(Forever).await;
+                assert!(!cached.is_loading());
+            }
+        });
+        runtime::run_until_stalled();
+        assert!(client.get_query_data(&Forever).unwrap().is_loading());
+
+        client.cancel_query(&Forever);
+        runtime::run_until_stalled();
+        let cached = client.get_query_data(&Forever).unwrap();
+        assert!(!cached.is_loading());
+        assert!(!cached.is_valid());
+        assert_eq!(cached.data(), Some(&1));
+    }
+
+    #[test]
+    fn cancels_queries_nobody_is_waiting_for() {
+        let client = QueryClient::new();
+        assert!(client.fetch_query(Forever).now_or_never().is_none());
+        assert!(client.get_query_data(&Forever).unwrap().is_idle());
+    }
+
+    #[test]
+    fn notifies_subscribers() {
+        let client = QueryClient::new();
+        let counter = Counter::default();
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let subscription = client.subscribe_query(counter.clone(), &Options::default(), {
+            let events = events.clone();
+            move |cached: Option<Cached<Counter>>| {
+                let event = cached.map(|cached| (cached.data().copied(), cached.is_loading()));
+                events.borrow_mut().push(event);
+            }
+        });
+
+        client.set_query_data(counter.clone(), 5);
+        client.invalidate_query(&counter);
+        runtime::block_on(client.fetch_query(counter.clone()));
+        client.remove_query(&counter);
+        assert_eq!(
+            *events.borrow(),
+            [
+                Some((Some(5), false)),
+                Some((Some(5), false)),
+                Some((Some(5), true)),
+                Some((Some(1), false)),
+                None,
+            ]
+        );
+
+        drop(subscription);
+        client.set_query_data(counter, 5);
+        assert_eq!(events.borrow().len(), 5);
+    }
+
+    #[test]
+    fn refetches_observed_queries_when_invalidated() {
+        let client = QueryClient::new();
+        let counter = Counter::default();
+        runtime::block_on(client.fetch_query(counter.clone()));
+        client.invalidate_query(&counter);
+        runtime::run_until_stalled();
+        assert_eq!(counter.0.get(), 1);
+
+        let disabled = Options {
+            enabled: Some(false),
+            ..Default::default()
+        };
+        let _disabled = client.subscribe_query(counter.clone(), &disabled, Callback::noop());
+        client.invalidate_query(&counter);
+        runtime::run_until_stalled();
+        assert_eq!(counter.0.get(), 1);
+
+        let _enabled =
+            client.subscribe_query(counter.clone(), &Options::default(), Callback::noop());
+        client.invalidate_query(&counter);
+        runtime::run_until_stalled();
+        assert_eq!(counter.0.get(), 2);
+        assert!(client.get_query_data(&counter).unwrap().is_valid());
+    }
+
+    #[test]
+    fn refetches_with_the_latest_shared_options() {
+        let client = QueryClient::builder()
+            .retry_delay(RetryDelay::Fixed(Duration::ZERO))
+            .build();
+        let query = Flaky::default();
+        let options = Rc::new(RefCell::new(Options {
+            retry: Some(Retry::Count(0)),
+            ..Default::default()
+        }));
+        let _subscription = client.subscribe_query_with_shared_options(
+            query.clone(),
+            options.clone(),
+            Callback::noop(),
+        );
+        for _ in 0..2 {
+            runtime::block_on(client.fetch_query_with_options(query.clone(), &options.borrow()));
+        }
+        assert_eq!(client.get_query_data(&query).unwrap().data(), Some(&2));
+
+        // Replaced as a hook's options are on every render.
+        *options.borrow_mut() = Options {
+            retry: Some(Retry::when(|_, _: &u32| true)),
+            ..Default::default()
+        };
+        client.invalidate_query(&query);
+        runtime::run_until_stalled();
+        assert_eq!(client.get_query_data(&query).unwrap().data(), Some(&4));
+    }
+
+    /// Completes each fetch with the value sent by the test.
+    #[derive(Clone, Default)]
+    struct Gated(Rc<RefCell<Vec<oneshot::Sender<u32>>>>);
+
+    impl Gated {
+        fn resolve(&self, data: u32) {
+            for sender in self.0.take() {
+                let _ = sender.send(data);
+            }
+        }
+    }
+
+    impl PartialEq for Gated {
+        fn eq(&self, _: &Self) -> bool {
+            true
+        }
+    }
+
+    impl Eq for Gated {}
+
+    impl std::hash::Hash for Gated {
+        fn hash<H: std::hash::Hasher>(&self, _: &mut H) {}
+    }
+
+    impl Query for Gated {
+        type Output = u32;
+        type Future = Pin<Box<dyn Future<Output = u32>>>;
+
+        fn query(&self) -> Self::Future {
+            let (sender, receiver) = oneshot::channel();
+            self.0.borrow_mut().push(sender);
+            Box::pin(receiver.map(Result::unwrap_or_default))
+        }
+    }
+
+    fn spawn_fetch<Q: TryQuery + 'static>(client: &QueryClient, query: Q) {
+        let client = client.clone();
+        runtime::spawn_local(async move {
+            client.fetch_query(query).await;
+        });
+        runtime::run_until_stalled();
+    }
+
+    #[test]
+    fn rolls_back_optimistic_updates() {
+        let client = QueryClient::new();
+        client.set_query_data(Number(1), 1);
+        let notified = Rc::new(Cell::new(0));
+        let _subscription = client.subscribe_query(Number(1), &Options::default(), {
+            let notified = notified.clone();
+            move |_| notified.set(notified.get() + 1)
+        });
+
+        let update = client.optimistic_update();
+        update.update_query_data(Number(1), |data| data.unwrap() + 1);
+        update.set_query_data(Number(2), 20);
+        assert_eq!(client.get_query_data(&Number(1)).unwrap().data(), Some(&2));
+        assert_eq!(notified.get(), 1);
+
+        update.rollback();
+        assert_eq!(client.get_query_data(&Number(1)).unwrap().data(), Some(&1));
+        assert!(client.get_query_data(&Number(2)).is_none());
+        assert_eq!(notified.get(), 2);
+    }
+
+    #[test]
+    fn does_not_overwrite_optimistic_updates() {
+        let client = QueryClient::new();
+        let query = Gated::default();
+        client.set_query_data(query.clone(), 1);
+        client.invalidate_query(&query);
+        spawn_fetch(&client, query.clone());
+
+        let update = client.optimistic_update();
+        update.set_query_data(query.clone(), 10);
+        query.resolve(2);
+        runtime::run_until_stalled();
+        let cached = client.get_query_data(&query).unwrap();
+        assert_eq!(cached.data(), Some(&10));
+        assert!(!cached.is_loading());
+
+        update.commit();
+        assert_eq!(client.get_query_data(&query).unwrap().data(), Some(&10));
+        client.invalidate_query(&query);
+        spawn_fetch(&client, query.clone());
+        query.resolve(3);
+        runtime::run_until_stalled();
+        assert_eq!(client.get_query_data(&query).unwrap().data(), Some(&3));
+    }
+
+    #[test]
+    fn rolls_back_optimistic_updates_of_loading_queries() {
+        let client = QueryClient::new();
+        let query = Gated::default();
+        spawn_fetch(&client, query.clone());
+
+        let update = client.optimistic_update();
+        update.set_query_data(query.clone(), 10);
+        update.rollback();
+        let cached = client.get_query_data(&query).unwrap();
+        assert!(cached.is_loading());
+        assert_eq!(cached.data(), None);
+
+        query.resolve(1);
+        runtime::run_until_stalled();
+        assert_eq!(client.get_query_data(&query).unwrap().data(), Some(&1));
+
+        // The fetch finished during the update, so there is nothing left to wait for.
+        client.invalidate_query(&query);
+        spawn_fetch(&client, query.clone());
+        let update = client.optimistic_update();
+        update.set_query_data(query.clone(), 10);
+        query.resolve(2);
+        runtime::run_until_stalled();
+        update.rollback();
+        let cached = client.get_query_data(&query).unwrap();
+        assert!(!cached.is_loading());
+        assert_eq!(cached.data(), Some(&1));
+    }
+
+    /// Pages numbered from 0 up to 3, starting at 1.
+    #[derive(Clone, Eq, PartialEq, Hash, Debug)]
+    struct Pages;
+
+    impl InfiniteQuery for Pages {
+        type PageParam = u32;
+        type Ok = u32;
+        type Error = ();
+        type Future = Ready<Result<u32, ()>>;
+
+        fn initial_page_param(&self) -> u32 {
+            1
+        }
+
+        fn query_page(&self, page_param: &u32) -> Self::Future {
+            ready(Ok(*page_param))
+        }
+
+        fn next_page_param(&self, last_page: &u32) -> Option<u32> {
+            Some(last_page + 1).filter(|page| *page <= 3)
+        }
+
+        fn previous_page_param(&self, first_page: &u32) -> Option<u32> {
+            first_page.checked_sub(1)
+        }
+    }
+
+    #[test]
+    fn fetches_pages() {
+        let client = QueryClient::new();
+        let options = Options::default();
+        let cached = runtime::block_on(client.fetch_query(Infinite(Pages)));
+        assert_eq!(cached.data().unwrap().page_params, [1]);
+        runtime::block_on(client.fetch_next_page(Pages, &options));
+        runtime::block_on(client.fetch_previous_page(Pages, &options));
+        runtime::block_on(client.fetch_next_page(Pages, &options));
+        // There are no more pages in either direction.
+        runtime::block_on(client.fetch_next_page(Pages, &options));
+        let cached = runtime::block_on(client.fetch_previous_page(Pages, &options));
+        let data = cached.data().unwrap();
+        assert_eq!(data.page_params, [0, 1, 2, 3]);
+        assert_eq!(
+            data.pages.iter().map(|page| **page).collect::<Vec<_>>(),
+            [0, 1, 2, 3]
+        );
+
+        client.invalidate_query(&Infinite(Pages));
+        let cached = runtime::block_on(client.fetch_query(Infinite(Pages)));
+        assert_eq!(cached.data().unwrap().page_params, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn applies_defaults_per_query_type() {
+        let client = QueryClient::builder()
+            .gc_time(Duration::ZERO)
+            .retry(Retry::Count(0))
+            .build();
+        let defaults = Options {
+            stale_time: Some(Duration::ZERO),
+            retry: Some(Retry::Count(1)),
+            ..Default::default()
+        };
+        client.set_query_defaults::<Counter>(defaults.clone());
+        assert_eq!(client.get_query_defaults::<Counter>(), defaults);
+        assert_eq!(client.get_query_defaults::<Number>(), Options::default());
+
+        let resolved = client.resolve_options::<Counter>(&Options {
+            retry: Some(Retry::Count(2)),
+            ..Default::default()
+        });
+        assert_eq!(resolved.stale_time, Duration::ZERO);
+        assert_eq!(resolved.gc_time, Duration::ZERO);
+        assert_eq!(resolved.retry, Retry::Count(2));
+        let resolved = client.resolve_options::<Number>(&Options::default());
+        assert_eq!(resolved.stale_time, Duration::MAX);
+        assert_eq!(resolved.retry, Retry::Count(0));
+
+        let counter = Counter::default();
+        runtime::block_on(client.fetch_query(counter.clone()));
+        let cached = runtime::block_on(client.fetch_query(counter));
+        assert_eq!(cached.data(), Some(&2));
+    }
+
+    #[test]
+    fn polls_once_per_query() {
+        let client = QueryClient::new();
+        let counter = Counter::default();
+        client.set_query_data(counter.clone(), 0);
+        let every = |millis| Options {
+            refetch_interval: Some(Duration::from_millis(millis)),
+            ..Default::default()
+        };
+        let is_polling = |interval| {
+            let client = client.0.borrow();
+            let polling = client.cache.polling(&counter);
+            polling.map(|polling| polling.is_polling(Duration::from_millis(interval), false))
+        };
+
+        let slow = client.subscribe_query(counter.clone(), &every(20), Callback::noop());
+        assert_eq!(is_polling(20), Some(true));
+        let fast = client.subscribe_query(counter.clone(), &every(5), Callback::noop());
+        let _unset = client.subscribe_query(counter.clone(), &Options::default(), Callback::noop());
+        assert_eq!(is_polling(5), Some(true));
+
+        runtime::block_on(clock::sleep(Duration::from_millis(12)));
+        assert!(counter.0.get() >= 1);
+        drop(fast);
+        assert_eq!(is_polling(20), Some(true));
+        drop(slow);
+        assert_eq!(is_polling(20), None);
+    }
+
+    #[test]
+    fn refetches_on_focus_and_reconnect() {
+        let focus_manager = ManualFocusManager::new();
+        let online_manager = ManualOnlineManager::new();
+        let client = QueryClient::builder()
+            .stale_time(Duration::ZERO)
+            .focus_manager(focus_manager.clone())
+            .online_manager(online_manager.clone())
+            .build();
+        let counter = Counter::default();
+        runtime::block_on(client.fetch_query(counter.clone()));
+        let _subscription =
+            client.subscribe_query(counter.clone(), &Options::default(), Callback::noop());
+
+        focus_manager.set_focused(false);
+        runtime::run_until_stalled();
+        assert_eq!(counter.0.get(), 1);
+        focus_manager.set_focused(true);
+        runtime::run_until_stalled();
+        assert_eq!(counter.0.get(), 2);
+
+        online_manager.set_online(false);
+        online_manager.set_online(true);
+        runtime::run_until_stalled();
+        assert_eq!(counter.0.get(), 3);
+
+        let _disabled = client.subscribe_query(
+            counter.clone(),
+            &Options {
+                refetch_on_window_focus: Some(false),
+                ..Default::default()
+            },
+            Callback::noop(),
+        );
+        focus_manager.set_focused(false);
+        focus_manager.set_focused(true);
+        runtime::run_until_stalled();
+        // Refetched once, for the subscriber which asked for it.
+        assert_eq!(counter.0.get(), 4);
+    }
+
+    #[test]
+    fn does_not_refetch_fresh_data_on_focus() {
+        let focus_manager = ManualFocusManager::new();
+        let client = QueryClient::builder()
+            .focus_manager(focus_manager.clone())
+            .build();
+        let counter = Counter::default();
+        runtime::block_on(client.fetch_query(counter.clone()));
+        let _subscription =
+            client.subscribe_query(counter.clone(), &Options::default(), Callback::noop());
+
+        focus_manager.set_focused(false);
+        focus_manager.set_focused(true);
+        runtime::run_until_stalled();
+        assert_eq!(counter.0.get(), 1);
+
+        client.0.borrow_mut().cache.invalidate(&counter);
+        focus_manager.set_focused(false);
+        focus_manager.set_focused(true);
+        runtime::run_until_stalled();
+        assert_eq!(counter.0.get(), 2);
+    }
+
+    #[test]
+    fn pauses_while_offline() {
+        let online_manager = ManualOnlineManager::new();
+        online_manager.set_online(false);
+        let client = QueryClient::builder()
+            .online_manager(online_manager.clone())
+            .build();
+        spawn_fetch(&client, Number(3));
+        let cached = client.get_query_data(&Number(3)).unwrap();
+        assert_eq!(cached.status(), QueryStatus::Pending);
+        assert_eq!(cached.fetch_status(), FetchStatus::Paused);
+
+        online_manager.set_online(true);
+        runtime::run_until_stalled();
+        let cached = client.get_query_data(&Number(3)).unwrap();
+        assert_eq!(cached.status(), QueryStatus::Success);
+        assert_eq!(cached.fetch_status(), FetchStatus::Idle);
+        assert_eq!(cached.data(), Some(&3));
+    }
+
+    #[test]
+    fn refetches_fresh_data() {
+        let client = QueryClient::new();
+        let counter = Rc::new(Counter::default());
+        runtime::block_on(client.refetch(counter.clone(), &Options::default()));
+        let cached = runtime::block_on(client.refetch(counter, &Options::default()));
+        assert_eq!(cached.data(), Some(&2));
+    }
+
+    #[test]
+    fn seeds_initial_data() {
+        let client = QueryClient::new();
+        let counter = Counter::default();
+        let options = Options {
+            initial_data: Some(InitialData::value(10u32)),
+            ..Default::default()
+        };
+        let cached = runtime::block_on(client.fetch_query_with_options(counter.clone(), &options));
+        assert_eq!(cached.data(), Some(&10));
+        assert_eq!(counter.0.get(), 0);
+
+        client.remove_query(&counter);
+        let options = Options {
+            initial_data: Some(
+                InitialData::with(|client| Some(*client.get_query_data(&Number(5))?.data()? + 1))
+                    .updated_at(0.0),
+            ),
+            stale_time: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        client.set_query_data(Number(5), 5);
+        let (data, updated_at) = client.initial_data(&counter, &options).unwrap();
+        assert_eq!((*data, updated_at), (6, 0.0));
+        assert!(client.get_query_data(&counter).is_none());
+        client.seed_initial_data(Rc::new(counter.clone()), &options);
+        assert_eq!(client.get_query_data(&counter).unwrap().data(), Some(&6));
+        // The initial data is long out of date, so it is refetched.
+        let cached = runtime::block_on(client.fetch_query_with_options(counter, &options));
+        assert_eq!(cached.data(), Some(&1));
+    }
+
+    /// Always fetches the same data, which is shared between fetches.
+    #[derive(Clone, Eq, PartialEq, Hash, Debug)]
+    struct Constant;
+
+    impl Query for Constant {
+        type Output = u32;
+        type Future = Ready<u32>;
+
+        fn query(&self) -> Self::Future {
+            ready(1)
+        }
+
+        fn share_structure(&self, previous: &Rc<u32>, next: Rc<u32>) -> Rc<u32> {
+            share_if_equal(previous, next)
+        }
+    }
+
+    #[test]
+    fn shares_unchanged_data() {
+        let client = QueryClient::new();
+        let first = runtime::block_on(client.fetch_query(Constant));
+        client.invalidate_query(&Constant);
+        let second = runtime::block_on(client.fetch_query(Constant));
+        assert!(std::ptr::eq(first.data().unwrap(), second.data().unwrap()));
+    }
+
+    #[test]
+    fn prefetches_and_ensures_query_data() {
+        let client = QueryClient::new();
+        let counter = Counter::default();
+        client.prefetch_query(counter.clone());
+        assert_eq!(counter.0.get(), 0);
+        runtime::run_until_stalled();
+        assert_eq!(client.get_query_data(&counter).unwrap().data(), Some(&1));
+
+        let data = runtime::block_on(client.ensure_query_data(counter.clone()));
+        assert_eq!(data.as_deref(), Some(&1));
+        let data = runtime::block_on(client.ensure_query_data(Number(2)));
+        assert_eq!(data.as_deref(), Some(&2));
+    }
+
+    /// Loads its first page immediately, and its other pages once resolved by the test.
+    #[derive(Clone, Default, Eq, PartialEq, Hash)]
+    struct GatedPages(Gated);
+
+    impl InfiniteQuery for GatedPages {
+        type PageParam = u32;
+        type Ok = u32;
+        type Error = ();
+        type Future = Pin<Box<dyn Future<Output = Result<u32, ()>>>>;
+
+        fn initial_page_param(&self) -> u32 {
+            0
+        }
+
+        fn query_page(&self, page_param: &u32) -> Self::Future {
+            match page_param {
+                0 => Box::pin(ready(Ok(0))),
+                _ => Box::pin(self.0.query().map(Ok)),
+            }
+        }
+
+        fn next_page_param(&self, last_page: &u32) -> Option<u32> {
+            Some(last_page + 1)
+        }
+    }
+
+    #[test]
+    fn tracks_the_page_being_fetched() {
+        let client = QueryClient::new();
+        let query = GatedPages::default();
+        let cached = runtime::block_on(client.fetch_query(Infinite(query.clone())));
+        assert_eq!(cached.fetching_page(), None);
+        spawn_local({
+            let client = client.clone();
+            let query = query.clone();
+            async move {
+                client.fetch_next_page(query, &Options::default()).await;
+            }
+        });
+        runtime::run_until_stalled();
+        let cached = client.get_query_data(&Infinite(query.clone())).unwrap();
+        assert_eq!(cached.fetching_page(), Some(Direction::Next));
+        assert_eq!(cached.data().map(|data| data.pages.len()), Some(1));
+
+        query.0.resolve(1);
+        runtime::run_until_stalled();
+        let cached = client.get_query_data(&Infinite(query)).unwrap();
+        assert_eq!(cached.fetching_page(), None);
+        assert_eq!(cached.data().map(|data| data.pages.len()), Some(2));
+    }
+
+    #[test]
+    fn fetches_a_page_once_when_it_is_already_being_fetched() {
+        let client = QueryClient::new();
+        let query = GatedPages::default();
+        runtime::block_on(client.fetch_query(Infinite(query.clone())));
+        for _ in 0..2 {
+            spawn_local({
+                let client = client.clone();
+                let query = query.clone();
+                async move {
+                    client.fetch_next_page(query, &Options::default()).await;
+                }
+            });
+        }
+        runtime::run_until_stalled();
+        query.0.resolve(1);
+        runtime::run_until_stalled();
+        let cached = client.get_query_data(&Infinite(query.clone())).unwrap();
+        assert_eq!(cached.fetching_page(), None);
+        assert_eq!(cached.data().map(|data| data.pages.len()), Some(2));
+        assert!(query.0 .0.borrow().is_empty());
+    }
+
+    #[test]
+    fn seeds_initial_data_of_the_query_type() {
+        let client = QueryClient::new();
+        let options = Options {
+            initial_data: Some(InitialData::value(4_u64)),
+            ..Default::default()
+        };
+        client.seed_initial_data(Rc::new(Number(4)), &options);
+        assert!(client.get_query_data(&Number(4)).is_none());
+        let options = Options {
+            initial_data: Some(InitialData::for_query(&Number(4), 4)),
+            ..Default::default()
+        };
+        client.seed_initial_data(Rc::new(Number(4)), &options);
+        let cached = client.get_query_data(&Number(4)).unwrap();
+        assert_eq!(cached.data(), Some(&4));
+    }
+}
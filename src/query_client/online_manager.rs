@@ -0,0 +1,107 @@
+use super::flag::Flag;
+use super::runtime;
+use super::Subscription;
+use gloo_events::EventListener;
+use std::cell::RefCell;
+use std::rc::Rc;
+use yew::Callback;
+
+/// Tracks whether the app has a network connection, so that queries can be refetched
+/// when it reconnects (see [`Options::refetch_on_reconnect`][super::Options::refetch_on_reconnect]).
+///
+/// By default, the [`QueryClient`][super::QueryClient] uses a [`NavigatorOnlineManager`][].
+/// Another implementation may be provided with [`QueryClientBuilder::online_manager`][super::QueryClientBuilder::online_manager].
+pub trait OnlineManager {
+    fn is_online(&self) -> bool;
+
+    /// Calls `callback` whenever the app goes online (`true`) or offline (`false`),
+    /// until the returned [`Subscription`][] is dropped.
+    fn subscribe(&self, callback: Callback<bool>) -> Subscription;
+}
+
+/// Tracks the browser's online status, as reported by `navigator.onLine`.
+#[derive(Clone)]
+pub struct NavigatorOnlineManager {
+    online: Flag,
+    listeners: Rc<RefCell<Vec<EventListener>>>,
+}
+
+impl NavigatorOnlineManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for NavigatorOnlineManager {
+    fn default() -> Self {
+        Self {
+            online: Flag::new(true),
+            listeners: Rc::default(),
+        }
+    }
+}
+
+impl OnlineManager for NavigatorOnlineManager {
+    fn is_online(&self) -> bool {
+        is_navigator_online()
+    }
+
+    fn subscribe(&self, callback: Callback<bool>) -> Subscription {
+        let mut listeners = self.listeners.borrow_mut();
+        if listeners.is_empty() {
+            if let Some(window) = runtime::window() {
+                self.online.set(window.navigator().on_line());
+                for (event_type, online) in [("online", true), ("offline", false)] {
+                    let flag = self.online.clone();
+                    listeners.push(EventListener::new(&window, event_type, move |_| {
+                        flag.set(online)
+                    }));
+                }
+            }
+        }
+        self.online.subscribe(callback)
+    }
+}
+
+fn is_navigator_online() -> bool {
+    runtime::window().is_none_or(|window| window.navigator().on_line())
+}
+
+/// Tracks online status as set manually with [`set_online`][ManualOnlineManager::set_online],
+/// such as in tests.
+///
+/// Clones share the same state, so a clone may be kept to drive the manager once
+/// it has been given to the [`QueryClient`][super::QueryClient].
+#[derive(Clone)]
+pub struct ManualOnlineManager {
+    online: Flag,
+}
+
+impl ManualOnlineManager {
+    /// Create a manager which is initially online.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_online(&self, online: bool) {
+        self.online.set(online);
+    }
+}
+
+impl Default for ManualOnlineManager {
+    fn default() -> Self {
+        Self {
+            online: Flag::new(true),
+        }
+    }
+}
+
+impl OnlineManager for ManualOnlineManager {
+    fn is_online(&self) -> bool {
+        self.online.get()
+    }
+
+    fn subscribe(&self, callback: Callback<bool>) -> Subscription {
+        self.online.subscribe(callback)
+    }
+}
@@ -0,0 +1,136 @@
+use super::{Cached, QueryClient};
+use crate::query::TryQuery;
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// A set of changes made to the cache in anticipation of a mutation succeeding,
+/// which can be undone if it does not.
+///
+/// The first time each query is touched by this update, its previous state is
+/// recorded. Until the update is [committed][OptimisticUpdate::commit] or
+/// [rolled back][OptimisticUpdate::rollback], fetches of the touched queries
+/// will not overwrite the optimistic data.
+///
+/// Dropping an `OptimisticUpdate` commits it.
+///
+/// See [`QueryClient::optimistic_update`][].
+pub struct OptimisticUpdate {
+    client: QueryClient,
+    touched: RefCell<Vec<Box<dyn Touched>>>,
+}
+
+/// A query touched by an optimistic update.
+trait Touched {
+    /// Allows the query to be fetched again.
+    fn release(&self, client: &QueryClient);
+    /// Puts the query back to its state from before it was touched.
+    fn restore(&self, client: &QueryClient);
+    fn notify(&self, client: &QueryClient);
+    fn as_any(&self) -> &dyn Any;
+}
+
+struct Previous<Q>
+where
+    Q: TryQuery,
+{
+    query: Rc<Q>,
+    cached: Option<Cached<Q>>,
+}
+
+impl<Q> Touched for Previous<Q>
+where
+    Q: TryQuery + 'static,
+{
+    fn release(&self, client: &QueryClient) {
+        client.0.borrow_mut().cache.release(self.query.as_ref());
+    }
+
+    fn restore(&self, client: &QueryClient) {
+        client
+            .0
+            .borrow_mut()
+            .cache
+            .restore(self.query.clone(), self.cached.clone());
+    }
+
+    fn notify(&self, client: &QueryClient) {
+        client.notify(self.query.as_ref());
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl OptimisticUpdate {
+    pub(super) fn new(client: QueryClient) -> Self {
+        Self {
+            client,
+            touched: RefCell::default(),
+        }
+    }
+
+    /// Optimistically set the data for a query, as with [`QueryClient::set_query_data`][].
+    pub fn set_query_data<Q: TryQuery + 'static>(&self, query: Q, data: Q::Ok) {
+        let query = Rc::new(query);
+        self.touch(query.clone());
+        self.client.set_query_data_rc(query, data);
+    }
+
+    /// Optimistically update the data for a query, based on its current data (if any).
+    pub fn update_query_data<Q: TryQuery + 'static>(
+        &self,
+        query: Q,
+        update: impl FnOnce(Option<&Q::Ok>) -> Q::Ok,
+    ) {
+        let data = {
+            let cached = self.client.get_query_data(&query);
+            update(cached.as_ref().and_then(|cached| cached.data()))
+        };
+        self.set_query_data(query, data);
+    }
+
+    fn touch<Q: TryQuery + 'static>(&self, query: Rc<Q>) {
+        let mut touched = self.touched.borrow_mut();
+        let already_touched = touched.iter().any(|touched| {
+            touched
+                .as_any()
+                .downcast_ref::<Previous<Q>>()
+                .is_some_and(|previous| previous.query == query)
+        });
+        if already_touched {
+            return;
+        }
+        let mut client = self.client.0.borrow_mut();
+        let cached = client.cache.get(query.as_ref());
+        client.cache.hold::<Q>(query.clone());
+        touched.push(Box::new(Previous { query, cached }));
+    }
+
+    /// Keeps the optimistic data, allowing the touched queries to be fetched again.
+    pub fn commit(self) {
+        // Committing is handled on drop.
+    }
+
+    /// Undoes the optimistic update, restoring every touched query to its previous
+    /// state all at once.
+    pub fn rollback(self) {
+        let touched = self.touched.take();
+        for previous in &touched {
+            previous.release(&self.client);
+            previous.restore(&self.client);
+        }
+        for previous in &touched {
+            previous.notify(&self.client);
+        }
+    }
+}
+
+impl Drop for OptimisticUpdate {
+    fn drop(&mut self) {
+        for previous in self.touched.take() {
+            previous.release(&self.client);
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use super::{InitialData, PlaceholderData, Retry, RetryDelay, ThrowOnError};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Options for customizing the behaviour of the query lifecycle.
+///
+/// Every option is optional, with `None` meaning the option is inherited. Options
+/// passed to a hook (or to [`QueryClient::fetch_query_with_options`][crate::query_client::QueryClient::fetch_query_with_options])
+/// are [merged][Options::merge] over the defaults for the query's type (see
+/// [`QueryClient::set_query_defaults`][crate::query_client::QueryClient::set_query_defaults]),
+/// then over the defaults set when building the [`QueryClient`][crate::query_client::QueryClient]
+/// (see [`QueryClientBuilder`][crate::query_client::QueryClientBuilder]), and anything
+/// left unset falls back to the defaults documented below.
+///
+/// Hooks always use the options from their latest render, so closures (such as
+/// [`Retry::If`][] or [`PlaceholderData::With`][]) may be created on every render.
+#[derive(Clone, Default, Eq, PartialEq, Debug)]
+pub struct Options {
+    /// Whether this query should be attempted at all right now.
+    ///
+    /// Default: `true`
+    pub enabled: Option<bool>,
+    /// How long fetched data is considered fresh. Once data is older than this,
+    /// it will be refetched (in the background) the next time the query is fetched.
+    ///
+    /// Default: [`Duration::MAX`][], so data never becomes stale on its own, and must
+    /// be invalidated using [`invalidate_query`][crate::query_client::QueryClient::invalidate_query].
+    pub stale_time: Option<Duration>,
+    /// How long the query's data is kept in the cache once it is no longer observed.
+    /// Once a query has been unobserved for this long, it is removed from the cache
+    /// as if by [`remove_query`][crate::query_client::QueryClient::remove_query].
+    /// Use [`Duration::MAX`][] to keep data forever.
+    ///
+    /// Default: 5 minutes
+    pub gc_time: Option<Duration>,
+    /// Whether a failed query should be attempted again.
+    ///
+    /// Default: retry 3 times
+    pub retry: Option<Retry>,
+    /// How long to wait between attempts of a failed query.
+    ///
+    /// Default: exponential backoff, starting at 1 second, up to 30 seconds
+    pub retry_delay: Option<RetryDelay>,
+    /// How often the query is refetched while it is being observed, regardless of
+    /// whether its data is stale. If several observers of the same query set an
+    /// interval, the query is refetched at the shortest of them. An interval of zero
+    /// is treated as [`Duration::MAX`][], rather than refetching continuously.
+    ///
+    /// Default: [`Duration::MAX`][], so the query is never refetched on an interval.
+    pub refetch_interval: Option<Duration>,
+    /// Whether the query continues to be refetched on its
+    /// [`refetch_interval`][Options::refetch_interval] while the app is not focused
+    /// (e.g. in a background tab). See [`FocusManager`][crate::query_client::FocusManager].
+    ///
+    /// Default: `false`
+    pub refetch_interval_in_background: Option<bool>,
+    /// Whether the query is refetched when the app regains focus, if it is being
+    /// observed and its data is stale.
+    ///
+    /// As data never becomes stale on its own by default, this only takes effect
+    /// for queries with a [`stale_time`][Options::stale_time], or which have been
+    /// invalidated. See [`FocusManager`][crate::query_client::FocusManager].
+    ///
+    /// Default: `true`
+    pub refetch_on_window_focus: Option<bool>,
+    /// Whether the query is refetched when the app reconnects to the network, if
+    /// it is being observed and its data is stale.
+    ///
+    /// As with [`refetch_on_window_focus`][Options::refetch_on_window_focus], this
+    /// only takes effect for queries with a [`stale_time`][Options::stale_time], or
+    /// which have been invalidated. See [`OnlineManager`][crate::query_client::OnlineManager].
+    ///
+    /// Default: `true`
+    pub refetch_on_reconnect: Option<bool>,
+    /// Data to show while the query has no data of its own.
+    ///
+    /// Default: `None`, so nothing is shown until the query has been fetched.
+    pub placeholder_data: Option<PlaceholderData>,
+    /// Data to seed the cache with when the query has no data yet.
+    ///
+    /// Default: `None`, so the query starts with no data.
+    pub initial_data: Option<InitialData>,
+    /// Whether a failed query is reported to the enclosing
+    /// [`QueryErrorBoundary`][crate::components::query_error_boundary::QueryErrorBoundary],
+    /// which then shows its fallback instead of the component using the query.
+    ///
+    /// Default: [`ThrowOnError::Never`][]
+    pub throw_on_error: Option<ThrowOnError>,
+}
+
+impl Options {
+    /// Combines these options with `defaults`, keeping every option which is set
+    /// here and taking the rest from `defaults`.
+    pub fn merge(&self, defaults: &Options) -> Options {
+        Options {
+            enabled: self.enabled.or(defaults.enabled),
+            stale_time: self.stale_time.or(defaults.stale_time),
+            gc_time: self.gc_time.or(defaults.gc_time),
+            retry: self.retry.clone().or_else(|| defaults.retry.clone()),
+            retry_delay: self
+                .retry_delay
+                .clone()
+                .or_else(|| defaults.retry_delay.clone()),
+            refetch_interval: self.refetch_interval.or(defaults.refetch_interval),
+            refetch_interval_in_background: self
+                .refetch_interval_in_background
+                .or(defaults.refetch_interval_in_background),
+            refetch_on_window_focus: self
+                .refetch_on_window_focus
+                .or(defaults.refetch_on_window_focus),
+            refetch_on_reconnect: self.refetch_on_reconnect.or(defaults.refetch_on_reconnect),
+            placeholder_data: self
+                .placeholder_data
+                .clone()
+                .or_else(|| defaults.placeholder_data.clone()),
+            initial_data: self
+                .initial_data
+                .clone()
+                .or_else(|| defaults.initial_data.clone()),
+            throw_on_error: self
+                .throw_on_error
+                .clone()
+                .or_else(|| defaults.throw_on_error.clone()),
+        }
+    }
+
+    /// Fills in any options which are not set with their defaults.
+    pub(crate) fn resolve(&self) -> ResolvedOptions {
+        ResolvedOptions {
+            enabled: self.enabled.unwrap_or(true),
+            stale_time: self.stale_time.unwrap_or(Duration::MAX),
+            gc_time: self.gc_time.unwrap_or(Duration::from_secs(5 * 60)),
+            retry: self.retry.clone().unwrap_or_default(),
+            retry_delay: self.retry_delay.clone().unwrap_or_default(),
+            refetch_interval: self
+                .refetch_interval
+                .filter(|interval| !interval.is_zero())
+                .unwrap_or(Duration::MAX),
+            refetch_interval_in_background: self.refetch_interval_in_background.unwrap_or(false),
+            refetch_on_window_focus: self.refetch_on_window_focus.unwrap_or(true),
+            refetch_on_reconnect: self.refetch_on_reconnect.unwrap_or(true),
+            placeholder_data: self.placeholder_data.clone(),
+            initial_data: self.initial_data.clone(),
+            throw_on_error: self.throw_on_error.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// [`Options`][] with every option set.
+#[derive(Clone, Debug)]
+pub(crate) struct ResolvedOptions {
+    pub enabled: bool,
+    pub stale_time: Duration,
+    pub gc_time: Duration,
+    pub retry: Retry,
+    pub retry_delay: RetryDelay,
+    pub refetch_interval: Duration,
+    pub refetch_interval_in_background: bool,
+    pub refetch_on_window_focus: bool,
+    pub refetch_on_reconnect: bool,
+    pub placeholder_data: Option<PlaceholderData>,
+    pub initial_data: Option<InitialData>,
+    pub throw_on_error: ThrowOnError,
+}
+
+/// Options which are replaced while in use, such as those of a mounted hook, which
+/// are replaced on every render. They are read whenever they are needed, so that
+/// the latest closures are used.
+pub(crate) type SharedOptions = Rc<RefCell<Options>>;
+
+/// [`Options`][] compared by value, treating any two closures of the same kind as equal.
+///
+/// Hooks observe a query again whenever their options change. A closure is only equal
+/// to itself, so one written inline would otherwise change on every render, observing
+/// (and re-rendering) over and over. The closures themselves are read from the
+/// hook's [`SharedOptions`][] instead.
+#[derive(Clone, Debug)]
+pub(crate) struct OptionsKey(pub Options);
+
+impl PartialEq for OptionsKey {
+    fn eq(&self, other: &Self) -> bool {
+        let (lhs, rhs) = (&self.0, &other.0);
+        lhs.enabled == rhs.enabled
+            && lhs.stale_time == rhs.stale_time
+            && lhs.gc_time == rhs.gc_time
+            && same_kind(&lhs.retry, &rhs.retry, |lhs, rhs| {
+                matches!((lhs, rhs), (Retry::If(..), Retry::If(..)))
+            })
+            && same_kind(&lhs.retry_delay, &rhs.retry_delay, |lhs, rhs| {
+                matches!((lhs, rhs), (RetryDelay::Custom(..), RetryDelay::Custom(..)))
+            })
+            && lhs.refetch_interval == rhs.refetch_interval
+            && lhs.refetch_interval_in_background == rhs.refetch_interval_in_background
+            && lhs.refetch_on_window_focus == rhs.refetch_on_window_focus
+            && lhs.refetch_on_reconnect == rhs.refetch_on_reconnect
+            && same_kind(&lhs.placeholder_data, &rhs.placeholder_data, |lhs, rhs| {
+                matches!(
+                    (lhs, rhs),
+                    (PlaceholderData::With(..), PlaceholderData::With(..))
+                )
+            })
+            // Initial data is only used when a query has no data, so is always read
+            // from the latest options rather than observing the query again.
+            && same_kind(&lhs.initial_data, &rhs.initial_data, |_, _| true)
+            && same_kind(&lhs.throw_on_error, &rhs.throw_on_error, |lhs, rhs| {
+                matches!((lhs, rhs), (ThrowOnError::If(..), ThrowOnError::If(..)))
+            })
+    }
+}
+
+impl Eq for OptionsKey {}
+
+/// Whether two options are equal, or hold closures which `closures_eq` considers
+/// to be of the same kind.
+fn same_kind<T: PartialEq>(
+    lhs: &Option<T>,
+    rhs: &Option<T>,
+    closures_eq: impl Fn(&T, &T) -> bool,
+) -> bool {
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => lhs == rhs || closures_eq(lhs, rhs),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_keeps_options_which_are_set() {
+        let options = Options {
+            stale_time: Some(Duration::from_secs(1)),
+            retry: Some(Retry::Count(1)),
+            ..Default::default()
+        };
+        let defaults = Options {
+            enabled: Some(false),
+            stale_time: Some(Duration::from_secs(2)),
+            retry: Some(Retry::Count(2)),
+            ..Default::default()
+        };
+        let merged = options.merge(&defaults);
+        assert_eq!(merged.enabled, Some(false));
+        assert_eq!(merged.stale_time, Some(Duration::from_secs(1)));
+        assert_eq!(merged.retry, Some(Retry::Count(1)));
+        assert_eq!(merged.gc_time, None);
+    }
+
+    #[test]
+    fn resolve_fills_in_defaults() {
+        let resolved = Options::default().resolve();
+        assert!(resolved.enabled);
+        assert_eq!(resolved.stale_time, Duration::MAX);
+        assert_eq!(resolved.gc_time, Duration::from_secs(5 * 60));
+        assert_eq!(resolved.retry, Retry::Count(3));
+        assert_eq!(resolved.placeholder_data, None);
+    }
+
+    #[test]
+    fn resolve_keeps_options_which_are_set() {
+        let resolved = Options {
+            enabled: Some(false),
+            gc_time: Some(Duration::ZERO),
+            placeholder_data: Some(PlaceholderData::KeepPrevious),
+            ..Default::default()
+        }
+        .resolve();
+        assert!(!resolved.enabled);
+        assert_eq!(resolved.gc_time, Duration::ZERO);
+        assert_eq!(
+            resolved.placeholder_data,
+            Some(PlaceholderData::KeepPrevious)
+        );
+    }
+
+    #[test]
+    fn resolve_disables_a_zero_refetch_interval() {
+        let resolve = |refetch_interval| {
+            Options {
+                refetch_interval: Some(refetch_interval),
+                ..Default::default()
+            }
+            .resolve()
+            .refetch_interval
+        };
+        assert_eq!(resolve(Duration::ZERO), Duration::MAX);
+        assert_eq!(resolve(Duration::from_millis(1)), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn keys_treat_closures_of_the_same_kind_as_equal() {
+        let retry = |retry| {
+            OptionsKey(Options {
+                retry: Some(retry),
+                ..Default::default()
+            })
+        };
+        assert_eq!(
+            retry(Retry::when(|_, _: &()| true)),
+            retry(Retry::when(|_, _: &()| false))
+        );
+        assert_ne!(retry(Retry::when(|_, _: &()| true)), retry(Retry::Count(1)));
+        assert_ne!(retry(Retry::Count(1)), retry(Retry::Count(2)));
+
+        let placeholder_data = |placeholder_data| {
+            OptionsKey(Options {
+                placeholder_data: Some(placeholder_data),
+                ..Default::default()
+            })
+        };
+        assert_eq!(
+            placeholder_data(PlaceholderData::value(1)),
+            placeholder_data(PlaceholderData::value(2))
+        );
+        assert_ne!(
+            placeholder_data(PlaceholderData::value(1)),
+            placeholder_data(PlaceholderData::KeepPrevious)
+        );
+
+        let throw_on_error = |throw_on_error| {
+            OptionsKey(Options {
+                throw_on_error: Some(throw_on_error),
+                ..Default::default()
+            })
+        };
+        assert_eq!(
+            throw_on_error(ThrowOnError::when(|_: &()| true)),
+            throw_on_error(ThrowOnError::when(|_: &()| false))
+        );
+        assert_ne!(
+            throw_on_error(ThrowOnError::when(|_: &()| true)),
+            throw_on_error(ThrowOnError::Always)
+        );
+    }
+}
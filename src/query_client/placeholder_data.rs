@@ -0,0 +1,67 @@
+use super::data_fn::{self, DataFn};
+use super::QueryClient;
+use crate::query::TryQuery;
+use std::fmt::{self, Debug, Formatter};
+use std::rc::Rc;
+
+/// Data to show for a query while it has no data of its own.
+///
+/// Placeholder data is never stored in the cache; it is only shown by the hook
+/// observing the query, flagged by [`QueryResult::is_placeholder_data`][crate::hooks::use_query::QueryResult::is_placeholder_data].
+#[derive(Clone)]
+pub enum PlaceholderData {
+    /// Keep showing the data from before the query changed (such as when moving to
+    /// the next page, or changing a filter) until the new query has data.
+    KeepPrevious,
+    /// Compute the data from the client, such as from the cached data of another query.
+    /// The data is ignored if it is not of the query's [`Ok`][crate::query::TryQuery::Ok] type.
+    ///
+    /// See [`PlaceholderData::value`][] and [`PlaceholderData::with`][] for more convenient
+    /// ways to construct this.
+    With(Rc<DataFn>),
+}
+
+impl PlaceholderData {
+    /// Show the given data for `query`, checking that it is of the query's
+    /// [`Ok`][crate::query::TryQuery::Ok] type.
+    pub fn for_query<Q: TryQuery>(_query: &Q, data: Q::Ok) -> Self
+    where
+        Q::Ok: 'static,
+    {
+        Self::value(data)
+    }
+
+    /// Show the given data.
+    ///
+    /// The data is ignored if it is not of the query's [`Ok`][crate::query::TryQuery::Ok]
+    /// type. Prefer [`PlaceholderData::for_query`][], which checks this.
+    pub fn value<T: 'static>(value: T) -> Self {
+        Self::With(data_fn::from_value(value))
+    }
+
+    /// Show the data returned by `f`, if any.
+    pub fn with<T: 'static>(f: impl Fn(&QueryClient) -> Option<T> + 'static) -> Self {
+        Self::With(data_fn::from_fn(f))
+    }
+}
+
+impl PartialEq for PlaceholderData {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::KeepPrevious, Self::KeepPrevious) => true,
+            (Self::With(lhs), Self::With(rhs)) => Rc::ptr_eq(lhs, rhs),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for PlaceholderData {}
+
+impl Debug for PlaceholderData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::KeepPrevious => f.write_str("KeepPrevious"),
+            Self::With(..) => f.debug_tuple("With").field(&"..").finish(),
+        }
+    }
+}
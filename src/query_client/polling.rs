@@ -0,0 +1,58 @@
+use super::runtime::spawn_local;
+use super::{clock, QueryClient};
+use crate::query::TryQuery;
+use futures::future::{abortable, AbortHandle};
+use futures::FutureExt;
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+/// Periodically refetches an observed query, until dropped.
+///
+/// There is at most one of these per query, however many observers request an interval.
+#[derive(Debug)]
+pub(crate) struct Polling {
+    interval: Duration,
+    in_background: bool,
+    abort_handle: AbortHandle,
+}
+
+impl Polling {
+    pub fn start<Q: TryQuery + 'static>(
+        client: &QueryClient,
+        query: Rc<Q>,
+        interval: Duration,
+        in_background: bool,
+    ) -> Self {
+        let client = Rc::downgrade(&client.0);
+        let (polling, abort_handle) = abortable(async move {
+            loop {
+                clock::sleep(interval).await;
+                let client = match Weak::upgrade(&client) {
+                    Some(client) => QueryClient(client),
+                    None => return,
+                };
+                let is_focused = client.0.borrow().focus_manager.is_focused();
+                if in_background || is_focused {
+                    client.invalidate_query(query.as_ref());
+                }
+            }
+        });
+        spawn_local(polling.map(|_| ()));
+        Self {
+            interval,
+            in_background,
+            abort_handle,
+        }
+    }
+
+    /// Whether this is already polling as requested.
+    pub fn is_polling(&self, interval: Duration, in_background: bool) -> bool {
+        self.interval == interval && self.in_background == in_background
+    }
+}
+
+impl Drop for Polling {
+    fn drop(&mut self) {
+        self.abort_handle.abort();
+    }
+}
@@ -1,31 +1,106 @@
-use futures::future::Shared;
+use crate::query::infinite::Direction;
+use futures::future::{AbortHandle, Abortable, Aborted, Shared};
 use futures::FutureExt;
+use std::cell::Cell;
 use std::future::Future;
 use std::pin::Pin;
 use std::rc::Rc;
 
+type Output<T, E> = Result<Result<Rc<T>, Rc<E>>, Aborted>;
+type BoxFuture<T, E> = Pin<Box<dyn Future<Output = Output<T, E>>>>;
+
+/// A shared, cancellable, in-flight query.
+///
+/// Resolves to `Err(Aborted)` if the request is cancelled before it completes.
 #[derive(Debug)]
-pub(crate) struct Request<T>(Shared<Pin<Box<dyn Future<Output = Rc<T>>>>>);
+pub(crate) struct Request<T, E> {
+    future: Shared<BoxFuture<T, E>>,
+    abort_handle: AbortHandle,
+    waiters: Rc<Cell<usize>>,
+    /// Whether the request is waiting for the network to come back before continuing.
+    paused: Rc<Cell<bool>>,
+    /// The page being fetched, if this request is fetching another page of an infinite query.
+    page: Option<Direction>,
+}
 
-impl<T> Clone for Request<T> {
+impl<T, E> Clone for Request<T, E> {
     fn clone(&self) -> Self {
-        Self(self.0.clone())
+        Self {
+            future: self.future.clone(),
+            abort_handle: self.abort_handle.clone(),
+            waiters: self.waiters.clone(),
+            paused: self.paused.clone(),
+            page: self.page,
+        }
     }
 }
 
-impl<T> Request<T> {
-    pub fn new(future: impl Future<Output = Rc<T>> + 'static) -> Self {
-        Self((Box::pin(future) as Pin<Box<dyn Future<Output = Rc<T>>>>).shared())
+impl<T, E> Request<T, E> {
+    pub fn new(future: impl Future<Output = Result<Rc<T>, Rc<E>>> + 'static) -> Self {
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        let future = Abortable::new(future, abort_registration);
+        Self {
+            future: (Box::pin(future) as BoxFuture<T, E>).shared(),
+            abort_handle,
+            waiters: Rc::new(Cell::new(0)),
+            paused: Rc::new(Cell::new(false)),
+            page: None,
+        }
+    }
+
+    /// Marks the request as fetching another page of an infinite query.
+    pub fn for_page(self, page: Option<Direction>) -> Self {
+        Self { page, ..self }
+    }
+
+    pub fn page(&self) -> Option<Direction> {
+        self.page
+    }
+
+    /// Stops the request. The underlying query future will not be polled again.
+    pub fn abort(&self) {
+        self.abort_handle.abort();
+    }
+
+    /// Whether the request has completed (or been aborted).
+    pub fn is_done(&self) -> bool {
+        self.abort_handle.is_aborted() || self.future.peek().is_some()
+    }
+
+    /// Whether two handles refer to the same request.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.waiters, &other.waiters)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.get()
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.set(paused);
+    }
+
+    /// Registers a caller that is waiting on this request.
+    pub fn add_waiter(&self) {
+        self.waiters.set(self.waiters.get() + 1);
+    }
+
+    /// Unregisters a caller that is waiting on this request. Returns `true` if
+    /// nobody is left waiting.
+    pub fn remove_waiter(&self) -> bool {
+        let waiters = self.waiters.get().saturating_sub(1);
+        self.waiters.set(waiters);
+        waiters == 0
     }
 }
 
-impl<T> Future for Request<T> {
-    type Output = Rc<T>;
+impl<T, E> Future for Request<T, E> {
+    type Output = Output<T, E>;
 
     fn poll(
         mut self: Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Self::Output> {
-        Pin::new(&mut self.0).poll(cx)
+        Pin::new(&mut self.future).poll(cx)
     }
 }
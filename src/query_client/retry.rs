@@ -0,0 +1,200 @@
+use super::{clock, runtime};
+use std::any::{type_name, Any};
+use std::fmt::{self, Debug, Formatter};
+use std::rc::Rc;
+use std::time::Duration;
+
+type RetryFn = dyn Fn(usize, &dyn Any) -> bool;
+
+/// Determines whether a failed query should be attempted again.
+#[derive(Clone)]
+pub enum Retry {
+    /// Retry up to this many times before giving up.
+    Count(usize),
+    /// Retry as long as this predicate returns `true`. It is provided the number
+    /// of times the query has failed so far, and the most recent error.
+    ///
+    /// See [`Retry::when`][] for a more convenient way to construct this.
+    If(Rc<RetryFn>),
+}
+
+impl Retry {
+    /// Retry as long as the predicate returns `true`, for queries whose error
+    /// is of type `E`. Queries with other error types are not retried, with a
+    /// warning in debug builds.
+    pub fn when<E: 'static>(predicate: impl Fn(usize, &E) -> bool + 'static) -> Self {
+        Self::If(Rc::new(move |failure_count, error| {
+            match error.downcast_ref::<E>() {
+                Some(error) => predicate(failure_count, error),
+                None => {
+                    runtime::warn(&format!(
+                        "not retrying a query whose error is not of type `{}`",
+                        type_name::<E>()
+                    ));
+                    false
+                }
+            }
+        }))
+    }
+
+    pub(crate) fn should_retry(&self, failure_count: usize, error: &dyn Any) -> bool {
+        match self {
+            Self::Count(count) => failure_count <= *count,
+            Self::If(predicate) => predicate(failure_count, error),
+        }
+    }
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self::Count(3)
+    }
+}
+
+impl PartialEq for Retry {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Count(lhs), Self::Count(rhs)) => lhs == rhs,
+            (Self::If(lhs), Self::If(rhs)) => Rc::ptr_eq(lhs, rhs),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Retry {}
+
+impl Debug for Retry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Count(count) => f.debug_tuple("Count").field(count).finish(),
+            Self::If(..) => f.debug_tuple("If").field(&"..").finish(),
+        }
+    }
+}
+
+/// Determines how long to wait before retrying a failed query.
+#[derive(Clone)]
+pub enum RetryDelay {
+    /// Always wait the same amount of time.
+    Fixed(Duration),
+    /// Wait `initial`, doubling after each failure up to `max`. A random jitter
+    /// of up to half the delay is subtracted, so that many failing queries do
+    /// not all retry at once.
+    Exponential { initial: Duration, max: Duration },
+    /// Compute the delay from the number of times the query has failed so far.
+    Custom(Rc<dyn Fn(usize) -> Duration>),
+}
+
+impl RetryDelay {
+    pub(crate) fn delay(&self, failure_count: usize) -> Duration {
+        match self {
+            Self::Fixed(delay) => *delay,
+            Self::Exponential { initial, max } => {
+                let exponent = failure_count.saturating_sub(1).min(31) as u32;
+                let delay = initial.saturating_mul(2u32.pow(exponent)).min(*max);
+                delay.mul_f64(1.0 - clock::random() / 2.0)
+            }
+            Self::Custom(delay) => delay(failure_count),
+        }
+    }
+}
+
+impl Default for RetryDelay {
+    fn default() -> Self {
+        Self::Exponential {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(30),
+        }
+    }
+}
+
+impl PartialEq for RetryDelay {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Fixed(lhs), Self::Fixed(rhs)) => lhs == rhs,
+            (
+                Self::Exponential { initial, max },
+                Self::Exponential {
+                    initial: other_initial,
+                    max: other_max,
+                },
+            ) => initial == other_initial && max == other_max,
+            (Self::Custom(lhs), Self::Custom(rhs)) => Rc::ptr_eq(lhs, rhs),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for RetryDelay {}
+
+impl Debug for RetryDelay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fixed(delay) => f.debug_tuple("Fixed").field(delay).finish(),
+            Self::Exponential { initial, max } => f
+                .debug_struct("Exponential")
+                .field("initial", initial)
+                .field("max", max)
+                .finish(),
+            Self::Custom(..) => f.debug_tuple("Custom").field(&"..").finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_up_to_count() {
+        let retry = Retry::Count(2);
+        assert!(retry.should_retry(1, &()));
+        assert!(retry.should_retry(2, &()));
+        assert!(!retry.should_retry(3, &()));
+    }
+
+    #[test]
+    fn retries_when_predicate_matches_error_type() {
+        let retry = Retry::when(|failure_count, error: &u16| failure_count < 3 && *error >= 500);
+        assert!(retry.should_retry(1, &503u16));
+        assert!(!retry.should_retry(1, &404u16));
+        assert!(!retry.should_retry(3, &503u16));
+        assert!(!retry.should_retry(1, &"another type"));
+    }
+
+    #[test]
+    fn fixed_delay() {
+        let delay = RetryDelay::Fixed(Duration::from_secs(2));
+        assert_eq!(delay.delay(1), Duration::from_secs(2));
+        assert_eq!(delay.delay(5), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn exponential_delay_doubles_with_jitter_up_to_max() {
+        let delay = RetryDelay::Exponential {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(10),
+        };
+        for (failure_count, full) in [(1, 1), (2, 2), (3, 4), (4, 8), (5, 10), (100, 10)] {
+            let full = Duration::from_secs(full);
+            let delay = delay.delay(failure_count);
+            assert!(delay <= full, "{delay:?} > {full:?}");
+            assert!(delay >= full / 2, "{delay:?} < {full:?} / 2");
+        }
+    }
+
+    #[test]
+    fn custom_delay() {
+        let delay = RetryDelay::Custom(Rc::new(|failure_count| {
+            Duration::from_millis(100 * failure_count as u64)
+        }));
+        assert_eq!(delay.delay(3), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn closures_are_compared_by_identity() {
+        let retry = Retry::when(|_, _: &()| true);
+        assert_eq!(retry, retry.clone());
+        assert_ne!(retry, Retry::when(|_, _: &()| true));
+    }
+}
@@ -0,0 +1,65 @@
+//! Access to the browser's event loop, window and console, with fallbacks for running tests
+//! outside of the browser.
+//!
+//! In tests which are not run in the browser, tasks are spawned onto a thread-local
+//! executor instead, which only makes progress while the test runs it with `block_on`
+//! or `run_until_stalled`. Outside of the browser, there is no window, and warnings
+//! are written to stderr.
+#[cfg(any(target_arch = "wasm32", not(test)))]
+pub(crate) use wasm_bindgen_futures::spawn_local;
+
+/// The browser window, if running in the browser.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn window() -> Option<web_sys::Window> {
+    web_sys::window()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn window() -> Option<web_sys::Window> {
+    None
+}
+
+/// Warns about a likely mistake in how the library is used, in debug builds only.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn warn(message: &str) {
+    if cfg!(debug_assertions) {
+        web_sys::console::warn_1(&format!("yew-query: {message}").into());
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn warn(message: &str) {
+    if cfg!(debug_assertions) {
+        eprintln!("yew-query: {message}");
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+thread_local! {
+    static EXECUTOR: (std::cell::RefCell<futures::executor::LocalPool>, futures::executor::LocalSpawner) = {
+        let pool = futures::executor::LocalPool::new();
+        let spawner = pool.spawner();
+        (std::cell::RefCell::new(pool), spawner)
+    };
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+pub(crate) fn spawn_local(future: impl std::future::Future<Output = ()> + 'static) {
+    use futures::task::LocalSpawnExt;
+    EXECUTOR.with(|(_, spawner)| {
+        // Spawning only fails once the executor has been dropped, as the thread exits.
+        let _ = spawner.spawn_local(future);
+    });
+}
+
+/// Runs the spawned tasks until `future` completes.
+#[cfg(all(test, not(target_arch = "wasm32")))]
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    EXECUTOR.with(|(pool, _)| pool.borrow_mut().run_until(future))
+}
+
+/// Runs the spawned tasks until none of them can make further progress.
+#[cfg(all(test, not(target_arch = "wasm32")))]
+pub(crate) fn run_until_stalled() {
+    EXECUTOR.with(|(pool, _)| pool.borrow_mut().run_until_stalled())
+}
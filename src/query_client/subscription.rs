@@ -0,0 +1,21 @@
+/// A subscription to changes of a query in the [`QueryClient`][super::QueryClient],
+/// or to a [`FocusManager`][super::FocusManager] or [`OnlineManager`][super::OnlineManager].
+///
+/// The subscription remains active until this value is dropped.
+#[must_use = "the subscription is cancelled when dropped"]
+pub struct Subscription(Option<Box<dyn FnOnce()>>);
+
+impl Subscription {
+    /// Creates a subscription which calls `unsubscribe` when dropped.
+    pub fn new(unsubscribe: impl FnOnce() + 'static) -> Self {
+        Self(Some(Box::new(unsubscribe)))
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(unsubscribe) = self.0.take() {
+            unsubscribe();
+        }
+    }
+}
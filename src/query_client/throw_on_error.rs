@@ -0,0 +1,85 @@
+use super::runtime;
+use std::any::{type_name, Any};
+use std::fmt::{self, Debug, Formatter};
+use std::rc::Rc;
+
+type ThrowOnErrorFn = dyn Fn(&dyn Any) -> bool;
+
+/// Determines whether a failed query is reported to the enclosing
+/// [`QueryErrorBoundary`][crate::components::query_error_boundary::QueryErrorBoundary],
+/// rather than being left for the component using it to handle.
+#[derive(Clone, Default)]
+pub enum ThrowOnError {
+    /// Leave errors for the component to handle.
+    #[default]
+    Never,
+    /// Report every error to the boundary.
+    Always,
+    /// Report errors for which this predicate returns `true`.
+    ///
+    /// See [`ThrowOnError::when`][] for a more convenient way to construct this.
+    If(Rc<ThrowOnErrorFn>),
+}
+
+impl ThrowOnError {
+    /// Report errors for which the predicate returns `true`, for queries whose error
+    /// is of type `E`. Errors of other types are left for the component to handle,
+    /// with a warning in debug builds.
+    pub fn when<E: 'static>(predicate: impl Fn(&E) -> bool + 'static) -> Self {
+        Self::If(Rc::new(move |error| match error.downcast_ref::<E>() {
+            Some(error) => predicate(error),
+            None => {
+                runtime::warn(&format!(
+                    "not throwing an error which is not of type `{}`",
+                    type_name::<E>()
+                ));
+                false
+            }
+        }))
+    }
+
+    pub(crate) fn should_throw(&self, error: &dyn Any) -> bool {
+        match self {
+            Self::Never => false,
+            Self::Always => true,
+            Self::If(predicate) => predicate(error),
+        }
+    }
+}
+
+impl PartialEq for ThrowOnError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Never, Self::Never) | (Self::Always, Self::Always) => true,
+            (Self::If(lhs), Self::If(rhs)) => Rc::ptr_eq(lhs, rhs),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for ThrowOnError {}
+
+impl Debug for ThrowOnError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Never => f.write_str("Never"),
+            Self::Always => f.write_str("Always"),
+            Self::If(..) => f.debug_tuple("If").field(&"..").finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throws_when_predicate_matches_error_type() {
+        let throw_on_error = ThrowOnError::when(|error: &u16| *error >= 500);
+        assert!(throw_on_error.should_throw(&503u16));
+        assert!(!throw_on_error.should_throw(&404u16));
+        assert!(!throw_on_error.should_throw(&"another type"));
+        assert!(ThrowOnError::Always.should_throw(&()));
+        assert!(!ThrowOnError::Never.should_throw(&()));
+    }
+}